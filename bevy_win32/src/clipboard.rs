@@ -0,0 +1,162 @@
+use std::sync::{Arc, Mutex};
+
+use bevy_ecs::prelude::*;
+use windows::Win32::{
+    Foundation::{GlobalFree, HANDLE, HGLOBAL, HWND},
+    System::{
+        DataExchange::{
+            CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, SetClipboardData,
+        },
+        Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+        Ole::CF_UNICODETEXT,
+    },
+};
+
+use crate::BevyWin32Error;
+
+/// A system clipboard that can hold text.
+pub trait ClipboardBackend: Send + Sync + 'static {
+    /// Gets the text currently on the clipboard, `None` if the clipboard holds no text.
+    fn get_text(&mut self) -> Result<Option<String>, BevyWin32Error>;
+
+    /// Replaces the contents of the clipboard with `text`.
+    fn set_text(&mut self, text: &str) -> Result<(), BevyWin32Error>;
+}
+
+/// A resource holding the [`ClipboardBackend`] used for copy and paste.
+///
+/// Defaults to [`Win32Clipboard`].
+#[derive(Resource)]
+pub struct Clipboard {
+    backend: Box<dyn ClipboardBackend>,
+}
+
+impl Clipboard {
+    pub fn new(backend: impl ClipboardBackend) -> Self {
+        Self {
+            backend: Box::new(backend),
+        }
+    }
+
+    /// Gets the text currently on the clipboard, failures are logged and treated as empty.
+    pub fn get_text(&mut self) -> Option<String> {
+        self.backend.get_text().unwrap_or_else(|err| {
+            log::warn!("failed to read clipboard: {}", err);
+            None
+        })
+    }
+
+    /// Sets the text on the clipboard, failures are logged.
+    pub fn set_text(&mut self, text: &str) {
+        if let Err(err) = self.backend.set_text(text) {
+            log::warn!("failed to write clipboard: {}", err);
+        }
+    }
+}
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        Self::new(Win32Clipboard)
+    }
+}
+
+/// A [`ClipboardBackend`] using the win32 clipboard with `CF_UNICODETEXT`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Win32Clipboard;
+
+impl ClipboardBackend for Win32Clipboard {
+    fn get_text(&mut self) -> Result<Option<String>, BevyWin32Error> {
+        unsafe {
+            OpenClipboard(HWND(0))?;
+            let text = match GetClipboardData(CF_UNICODETEXT.0 as u32) {
+                Ok(handle) => {
+                    let hglobal = HGLOBAL(handle.0 as _);
+                    let data = GlobalLock(hglobal) as *const u16;
+                    if data.is_null() {
+                        None
+                    } else {
+                        let len = (0..).take_while(|&i| *data.add(i) != 0).count();
+                        let text = String::from_utf16_lossy(std::slice::from_raw_parts(data, len));
+                        // NOTE: This "fails" once the lock count reaches zero, which is what we want.
+                        let _ = GlobalUnlock(hglobal);
+                        Some(text)
+                    }
+                }
+                // Nothing (or nothing textual) is on the clipboard.
+                Err(_) => None,
+            };
+            CloseClipboard()?;
+            Ok(text)
+        }
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), BevyWin32Error> {
+        let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            let hglobal = GlobalAlloc(GMEM_MOVEABLE, std::mem::size_of_val(wide.as_slice()))?;
+            let data = GlobalLock(hglobal) as *mut u16;
+            if data.is_null() {
+                let _ = GlobalFree(hglobal);
+                return Err(windows::core::Error::from_win32().into());
+            }
+            data.copy_from_nonoverlapping(wide.as_ptr(), wide.len());
+            let _ = GlobalUnlock(hglobal);
+
+            if let Err(err) = OpenClipboard(HWND(0)) {
+                let _ = GlobalFree(hglobal);
+                return Err(err.into());
+            }
+            let result = EmptyClipboard()
+                .and_then(|_| SetClipboardData(CF_UNICODETEXT.0 as u32, HANDLE(hglobal.0 as _)));
+            // The clipboard owns the memory once `SetClipboardData` succeeds.
+            if result.is_err() {
+                let _ = GlobalFree(hglobal);
+            }
+            CloseClipboard()?;
+            result?;
+        }
+        Ok(())
+    }
+}
+
+/// An in-memory [`ClipboardBackend`] that never touches the system clipboard.
+///
+/// Clones share the same contents.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryClipboard {
+    text: Arc<Mutex<Option<String>>>,
+}
+
+impl ClipboardBackend for MemoryClipboard {
+    fn get_text(&mut self) -> Result<Option<String>, BevyWin32Error> {
+        Ok(self.text.lock().unwrap().clone())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), BevyWin32Error> {
+        *self.text.lock().unwrap() = Some(text.to_owned());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_clipboard_round_trip() {
+        let memory = MemoryClipboard::default();
+        let mut clipboard = Clipboard::new(memory.clone());
+        assert_eq!(clipboard.get_text(), None);
+
+        clipboard.set_text("copied");
+        assert_eq!(clipboard.get_text(), Some("copied".to_owned()));
+        // Clones share the contents.
+        assert_eq!(
+            memory.clone().get_text().unwrap(),
+            Some("copied".to_owned())
+        );
+
+        clipboard.set_text("");
+        assert_eq!(clipboard.get_text(), Some(String::new()));
+    }
+}
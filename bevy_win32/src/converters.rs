@@ -1,8 +1,12 @@
 use bevy_input::keyboard::{KeyCode, ScanCode};
 use bevy_math::Vec2;
-use windows::Win32::{
-    Foundation::{LPARAM, WPARAM},
-    UI::{Input::KeyboardAndMouse::*, WindowsAndMessaging::WHEEL_DELTA},
+use bevy_window::CursorIcon;
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{LPARAM, WPARAM},
+        UI::{Input::KeyboardAndMouse::*, WindowsAndMessaging::*},
+    },
 };
 
 pub fn mouse_pos_from_lparam(lparam: LPARAM) -> Vec2 {
@@ -11,6 +15,13 @@ pub fn mouse_pos_from_lparam(lparam: LPARAM) -> Vec2 {
     Vec2::new(x, y)
 }
 
+/// Gets the new client area size in pixels from the `lparam` of a `WM_SIZE` message.
+pub fn size_from_lparam(lparam: LPARAM) -> (u32, u32) {
+    let width = (lparam.0 & 0xFFFF) as u32;
+    let height = (lparam.0 >> 16 & 0xFFFF) as u32;
+    (width, height)
+}
+
 // TODO: Read lower (wparam cast to i16) and see if that is ever more than 0 its the X coordinate.
 pub fn mouse_wheel_delta_from_wparam(wparam: WPARAM) -> f32 {
     let _x_coord = wparam.0 as i16;
@@ -18,6 +29,17 @@ pub fn mouse_wheel_delta_from_wparam(wparam: WPARAM) -> f32 {
     y_coord as f32 * 10. / WHEEL_DELTA as f32 // TODO: Why do we multiply by 10.?
 }
 
+/// Gets which extra mouse button (`XBUTTON1` is `1`, `XBUTTON2` is `2`) a `WM_XBUTTON*` message is for.
+pub fn xbutton_from_wparam(wparam: WPARAM) -> u16 {
+    (wparam.0 >> 16) as u16
+}
+
+/// Gets the new scale factor from the `wparam` of a `WM_DPICHANGED` message.
+pub fn scale_factor_from_dpi_wparam(wparam: WPARAM) -> f64 {
+    let dpi = (wparam.0 & 0xFFFF) as u32;
+    dpi as f64 / USER_DEFAULT_SCREEN_DPI as f64
+}
+
 pub fn scancode_from_lparam(lparam: LPARAM) -> ScanCode {
     let scancode = (lparam.0 >> 16) as u8;
     ScanCode(scancode.into())
@@ -173,3 +195,28 @@ pub fn keycode_from_wparam(wparam: WPARAM) -> Option<KeyCode> {
         _ => None,
     }
 }
+
+/// Gets the system cursor resource that best matches the [`CursorIcon`].
+pub fn cursor_name_from_icon(icon: CursorIcon) -> PCWSTR {
+    match icon {
+        CursorIcon::Crosshair | CursorIcon::Cell => IDC_CROSS,
+        CursorIcon::Hand | CursorIcon::Grab => IDC_HAND,
+        CursorIcon::Move | CursorIcon::Grabbing | CursorIcon::AllScroll => IDC_SIZEALL,
+        CursorIcon::Text | CursorIcon::VerticalText => IDC_IBEAM,
+        CursorIcon::Wait => IDC_WAIT,
+        CursorIcon::Help => IDC_HELP,
+        CursorIcon::Progress => IDC_APPSTARTING,
+        CursorIcon::NotAllowed | CursorIcon::NoDrop => IDC_NO,
+        CursorIcon::EResize
+        | CursorIcon::WResize
+        | CursorIcon::EwResize
+        | CursorIcon::ColResize => IDC_SIZEWE,
+        CursorIcon::NResize
+        | CursorIcon::SResize
+        | CursorIcon::NsResize
+        | CursorIcon::RowResize => IDC_SIZENS,
+        CursorIcon::NeResize | CursorIcon::SwResize | CursorIcon::NeswResize => IDC_SIZENESW,
+        CursorIcon::NwResize | CursorIcon::SeResize | CursorIcon::NwseResize => IDC_SIZENWSE,
+        _ => IDC_ARROW,
+    }
+}
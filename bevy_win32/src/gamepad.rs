@@ -0,0 +1,445 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bevy_ecs::{prelude::*, system::SystemParam};
+use bevy_input::{
+    gamepad::{
+        Gamepad, GamepadAxis, GamepadAxisChangedEvent, GamepadAxisType, GamepadButton,
+        GamepadButtonChangedEvent, GamepadButtonType, GamepadConnection, GamepadConnectionEvent,
+        GamepadInfo, GamepadSettings, Gamepads,
+    },
+    Axis,
+};
+use bevy_utils::HashMap;
+use windows::Win32::{Foundation::ERROR_SUCCESS, UI::Input::XboxController::*};
+
+/// A snapshot of the raw button and axis values of a single gamepad.
+///
+/// Buttons are in the range `0.0..=1.0`, axes in the range `-1.0..=1.0`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GamepadState {
+    pub buttons: HashMap<GamepadButtonType, f32>,
+    pub axes: HashMap<GamepadAxisType, f32>,
+}
+
+/// A source of gamepad state that [`poll_gamepads`] reads from every frame.
+pub trait GamepadBackend: Send + Sync + 'static {
+    /// The number of gamepad slots this backend can report on.
+    fn slots(&self) -> usize;
+
+    /// Polls the gamepad in `slot`, returning `None` if it is not connected.
+    fn poll(&mut self, slot: usize) -> Option<GamepadState>;
+
+    /// A human readable name for the gamepad in `slot`.
+    fn name(&self, slot: usize) -> String;
+}
+
+/// A resource holding the [`GamepadBackend`] that gamepads are polled through.
+///
+/// Defaults to [`XInputBackend`].
+#[derive(Resource)]
+pub struct Win32Gamepads {
+    backend: Box<dyn GamepadBackend>,
+}
+
+impl Win32Gamepads {
+    pub fn new(backend: impl GamepadBackend) -> Self {
+        Self {
+            backend: Box::new(backend),
+        }
+    }
+
+    pub fn backend_mut(&mut self) -> &mut dyn GamepadBackend {
+        self.backend.as_mut()
+    }
+}
+
+impl Default for Win32Gamepads {
+    fn default() -> Self {
+        Self::new(XInputBackend::default())
+    }
+}
+
+/// How long to wait before probing a disconnected XInput slot again.
+///
+/// Calling `XInputGetState` on an empty slot is expensive, so we avoid doing it every frame.
+const XINPUT_PROBE_INTERVAL: Duration = Duration::from_secs(1);
+
+const XINPUT_BUTTONS: [(XINPUT_GAMEPAD_BUTTON_FLAGS, GamepadButtonType); 14] = [
+    (XINPUT_GAMEPAD_A, GamepadButtonType::South),
+    (XINPUT_GAMEPAD_B, GamepadButtonType::East),
+    (XINPUT_GAMEPAD_X, GamepadButtonType::West),
+    (XINPUT_GAMEPAD_Y, GamepadButtonType::North),
+    (XINPUT_GAMEPAD_LEFT_SHOULDER, GamepadButtonType::LeftTrigger),
+    (
+        XINPUT_GAMEPAD_RIGHT_SHOULDER,
+        GamepadButtonType::RightTrigger,
+    ),
+    (XINPUT_GAMEPAD_BACK, GamepadButtonType::Select),
+    (XINPUT_GAMEPAD_START, GamepadButtonType::Start),
+    (XINPUT_GAMEPAD_LEFT_THUMB, GamepadButtonType::LeftThumb),
+    (XINPUT_GAMEPAD_RIGHT_THUMB, GamepadButtonType::RightThumb),
+    (XINPUT_GAMEPAD_DPAD_UP, GamepadButtonType::DPadUp),
+    (XINPUT_GAMEPAD_DPAD_DOWN, GamepadButtonType::DPadDown),
+    (XINPUT_GAMEPAD_DPAD_LEFT, GamepadButtonType::DPadLeft),
+    (XINPUT_GAMEPAD_DPAD_RIGHT, GamepadButtonType::DPadRight),
+];
+
+/// When each empty slot was last probed, so it is only probed again after [`XINPUT_PROBE_INTERVAL`].
+#[derive(Debug, Default)]
+struct SlotProbes([Option<Instant>; XUSER_MAX_COUNT as usize]);
+
+impl SlotProbes {
+    /// Whether `slot` should be probed at `now`.
+    fn is_due(&self, slot: usize, now: Instant) -> bool {
+        match self.0[slot] {
+            Some(last_probe) => now.saturating_duration_since(last_probe) >= XINPUT_PROBE_INTERVAL,
+            None => true,
+        }
+    }
+
+    /// Remembers probing `slot` at `now`, connected slots are polled every frame.
+    fn probed(&mut self, slot: usize, now: Instant, connected: bool) {
+        self.0[slot] = (!connected).then_some(now);
+    }
+}
+
+/// A [`GamepadBackend`] reading controllers through XInput.
+#[derive(Debug, Default)]
+pub struct XInputBackend {
+    probes: SlotProbes,
+}
+
+impl GamepadBackend for XInputBackend {
+    fn slots(&self) -> usize {
+        XUSER_MAX_COUNT as usize
+    }
+
+    fn poll(&mut self, slot: usize) -> Option<GamepadState> {
+        let now = Instant::now();
+        if !self.probes.is_due(slot, now) {
+            return None;
+        }
+
+        let mut state = XINPUT_STATE::default();
+        let connected = unsafe { XInputGetState(slot as u32, &mut state) } == ERROR_SUCCESS.0;
+        self.probes.probed(slot, now, connected);
+        if !connected {
+            return None;
+        }
+
+        let pad = state.Gamepad;
+        let mut buttons: HashMap<_, _> = XINPUT_BUTTONS
+            .iter()
+            .map(|(flag, button_type)| {
+                let pressed = pad.wButtons.0 & flag.0 != 0;
+                (*button_type, if pressed { 1.0 } else { 0.0 })
+            })
+            .collect();
+        buttons.insert(
+            GamepadButtonType::LeftTrigger2,
+            pad.bLeftTrigger as f32 / u8::MAX as f32,
+        );
+        buttons.insert(
+            GamepadButtonType::RightTrigger2,
+            pad.bRightTrigger as f32 / u8::MAX as f32,
+        );
+
+        let thumb = |v: i16| (v as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+        let axes = HashMap::from_iter([
+            (GamepadAxisType::LeftStickX, thumb(pad.sThumbLX)),
+            (GamepadAxisType::LeftStickY, thumb(pad.sThumbLY)),
+            (GamepadAxisType::RightStickX, thumb(pad.sThumbRX)),
+            (GamepadAxisType::RightStickY, thumb(pad.sThumbRY)),
+        ]);
+
+        Some(GamepadState { buttons, axes })
+    }
+
+    fn name(&self, slot: usize) -> String {
+        format!("XInput Controller {}", slot + 1)
+    }
+}
+
+/// An in-memory [`GamepadBackend`], useful for driving gamepad input without hardware.
+///
+/// Clones share the same slots, so keep a clone around to change the state after handing
+/// one to [`Win32Gamepads::new`].
+#[derive(Debug, Default, Clone)]
+pub struct MemoryGamepadBackend {
+    slots: Arc<Mutex<Vec<Option<MemoryGamepad>>>>,
+}
+
+#[derive(Debug, Clone)]
+struct MemoryGamepad {
+    name: String,
+    state: GamepadState,
+}
+
+impl MemoryGamepadBackend {
+    /// Connects a gamepad in `slot` with an empty state.
+    pub fn connect(&self, slot: usize, name: impl Into<String>) {
+        let mut slots = self.slots.lock().unwrap();
+        if slots.len() <= slot {
+            slots.resize(slot + 1, None);
+        }
+        slots[slot] = Some(MemoryGamepad {
+            name: name.into(),
+            state: GamepadState::default(),
+        });
+    }
+
+    pub fn disconnect(&self, slot: usize) {
+        if let Some(entry) = self.slots.lock().unwrap().get_mut(slot) {
+            *entry = None;
+        }
+    }
+
+    pub fn set_button(&self, slot: usize, button_type: GamepadButtonType, value: f32) {
+        self.with_state(slot, |state| {
+            state.buttons.insert(button_type, value);
+        })
+    }
+
+    pub fn set_axis(&self, slot: usize, axis_type: GamepadAxisType, value: f32) {
+        self.with_state(slot, |state| {
+            state.axes.insert(axis_type, value);
+        })
+    }
+
+    fn with_state(&self, slot: usize, f: impl FnOnce(&mut GamepadState)) {
+        if let Some(Some(gamepad)) = self.slots.lock().unwrap().get_mut(slot) {
+            f(&mut gamepad.state)
+        }
+    }
+}
+
+impl GamepadBackend for MemoryGamepadBackend {
+    fn slots(&self) -> usize {
+        self.slots.lock().unwrap().len()
+    }
+
+    fn poll(&mut self, slot: usize) -> Option<GamepadState> {
+        let slots = self.slots.lock().unwrap();
+        slots
+            .get(slot)?
+            .as_ref()
+            .map(|gamepad| gamepad.state.clone())
+    }
+
+    fn name(&self, slot: usize) -> String {
+        let slots = self.slots.lock().unwrap();
+        match slots.get(slot) {
+            Some(Some(gamepad)) => gamepad.name.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+#[derive(SystemParam)]
+pub struct GamepadEventWriters<'w> {
+    connection: EventWriter<'w, GamepadConnectionEvent>,
+    button: EventWriter<'w, GamepadButtonChangedEvent>,
+    axis: EventWriter<'w, GamepadAxisChangedEvent>,
+}
+
+/// Polls every slot of the [`GamepadBackend`] and emits the bevy gamepad events for anything that changed.
+///
+/// Values are filtered through the [`GamepadSettings`] the same way `bevy_gilrs` does.
+pub fn poll_gamepads(
+    mut win32_gamepads: ResMut<Win32Gamepads>,
+    gamepads: Res<Gamepads>,
+    settings: Res<GamepadSettings>,
+    gamepad_axis: Res<Axis<GamepadAxis>>,
+    gamepad_buttons: Res<Axis<GamepadButton>>,
+    mut event_writers: GamepadEventWriters,
+) {
+    let backend = win32_gamepads.backend_mut();
+    for slot in 0..backend.slots() {
+        let gamepad = Gamepad::new(slot);
+        let state = match backend.poll(slot) {
+            Some(state) => state,
+            None => {
+                if gamepads.contains(gamepad) {
+                    log::info!("gamepad {} disconnected", slot);
+                    event_writers.connection.send(GamepadConnectionEvent::new(
+                        gamepad,
+                        GamepadConnection::Disconnected,
+                    ));
+                }
+                continue;
+            }
+        };
+
+        if !gamepads.contains(gamepad) {
+            let name = backend.name(slot);
+            log::info!("gamepad {} connected ({})", slot, name);
+            event_writers.connection.send(GamepadConnectionEvent::new(
+                gamepad,
+                GamepadConnection::Connected(GamepadInfo { name }),
+            ));
+        }
+
+        for (button_type, raw_value) in state.buttons {
+            let button = GamepadButton::new(gamepad, button_type);
+            let old_value = gamepad_buttons.get(button);
+            if let Some(value) = settings
+                .get_button_axis_settings(button)
+                .filter(raw_value, old_value)
+            {
+                event_writers.button.send(GamepadButtonChangedEvent::new(
+                    gamepad,
+                    button_type,
+                    value,
+                ));
+            }
+        }
+
+        for (axis_type, raw_value) in state.axes {
+            let axis = GamepadAxis::new(gamepad, axis_type);
+            let old_value = gamepad_axis.get(axis);
+            if let Some(value) = settings
+                .get_axis_settings(axis)
+                .filter(raw_value, old_value)
+            {
+                event_writers
+                    .axis
+                    .send(GamepadAxisChangedEvent::new(gamepad, axis_type, value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::prelude::*;
+    use bevy_input::{Input, InputPlugin, InputSystem};
+
+    use super::*;
+
+    fn app(backend: &MemoryGamepadBackend) -> App {
+        let mut app = App::new();
+        app.add_plugins(InputPlugin)
+            .insert_resource(Win32Gamepads::new(backend.clone()))
+            .add_systems(PreUpdate, poll_gamepads.before(InputSystem));
+        app
+    }
+
+    fn drain<T: Event>(app: &mut App) -> Vec<T> {
+        app.world.resource_mut::<Events<T>>().drain().collect()
+    }
+
+    #[test]
+    fn connect_and_disconnect() {
+        let backend = MemoryGamepadBackend::default();
+        let mut app = app(&backend);
+        let gamepad = Gamepad::new(1);
+
+        backend.connect(1, "Test Pad");
+        app.update();
+        let events = drain::<GamepadConnectionEvent>(&mut app);
+        assert_eq!(
+            events,
+            vec![GamepadConnectionEvent::new(
+                gamepad,
+                GamepadConnection::Connected(GamepadInfo {
+                    name: "Test Pad".to_owned()
+                }),
+            )]
+        );
+        assert!(app.world.resource::<Gamepads>().contains(gamepad));
+        assert!(!app.world.resource::<Gamepads>().contains(Gamepad::new(0)));
+
+        app.update();
+        assert!(drain::<GamepadConnectionEvent>(&mut app).is_empty());
+
+        backend.disconnect(1);
+        app.update();
+        let events = drain::<GamepadConnectionEvent>(&mut app);
+        assert_eq!(
+            events,
+            vec![GamepadConnectionEvent::new(
+                gamepad,
+                GamepadConnection::Disconnected
+            )]
+        );
+        assert!(!app.world.resource::<Gamepads>().contains(gamepad));
+
+        app.update();
+        assert!(drain::<GamepadConnectionEvent>(&mut app).is_empty());
+    }
+
+    #[test]
+    fn button_and_axis_changes() {
+        let backend = MemoryGamepadBackend::default();
+        let mut app = app(&backend);
+        let gamepad = Gamepad::new(0);
+        let south = GamepadButton::new(gamepad, GamepadButtonType::South);
+        let stick_x = GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX);
+
+        backend.connect(0, "Test Pad");
+        app.update();
+        drain::<GamepadConnectionEvent>(&mut app);
+
+        backend.set_button(0, GamepadButtonType::South, 1.0);
+        backend.set_axis(0, GamepadAxisType::LeftStickX, 0.5);
+        app.update();
+        assert_eq!(
+            drain::<GamepadButtonChangedEvent>(&mut app),
+            vec![GamepadButtonChangedEvent::new(
+                gamepad,
+                GamepadButtonType::South,
+                1.0
+            )]
+        );
+        assert_eq!(
+            drain::<GamepadAxisChangedEvent>(&mut app),
+            vec![GamepadAxisChangedEvent::new(
+                gamepad,
+                GamepadAxisType::LeftStickX,
+                0.5
+            )]
+        );
+        assert!(app.world.resource::<Input<GamepadButton>>().pressed(south));
+        assert_eq!(
+            app.world.resource::<Axis<GamepadAxis>>().get(stick_x),
+            Some(0.5)
+        );
+
+        // Unchanged values are filtered out.
+        app.update();
+        assert!(drain::<GamepadButtonChangedEvent>(&mut app).is_empty());
+        assert!(drain::<GamepadAxisChangedEvent>(&mut app).is_empty());
+
+        backend.set_button(0, GamepadButtonType::South, 0.0);
+        app.update();
+        assert_eq!(
+            drain::<GamepadButtonChangedEvent>(&mut app),
+            vec![GamepadButtonChangedEvent::new(
+                gamepad,
+                GamepadButtonType::South,
+                0.0
+            )]
+        );
+        assert!(!app.world.resource::<Input<GamepadButton>>().pressed(south));
+    }
+
+    #[test]
+    fn empty_slots_are_probed_every_interval() {
+        let mut probes = SlotProbes::default();
+        let start = Instant::now();
+
+        assert!(probes.is_due(0, start));
+        probes.probed(0, start, false);
+        assert!(!probes.is_due(0, start));
+        assert!(!probes.is_due(0, start + XINPUT_PROBE_INTERVAL / 2));
+        assert!(probes.is_due(0, start + XINPUT_PROBE_INTERVAL));
+        // Other slots keep their own time.
+        assert!(probes.is_due(1, start));
+
+        probes.probed(0, start + XINPUT_PROBE_INTERVAL, true);
+        assert!(probes.is_due(0, start + XINPUT_PROBE_INTERVAL));
+    }
+}
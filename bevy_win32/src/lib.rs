@@ -4,132 +4,313 @@ use bevy_input::{
     keyboard::KeyboardInput,
     mouse::{MouseButtonInput, MouseMotion, MouseScrollUnit, MouseWheel},
     prelude::*,
-    ButtonState,
+    ButtonState, InputSystem,
 };
 
-use bevy_window::{prelude::*, PrimaryWindow};
+use bevy_window::{
+    prelude::*, PrimaryWindow, WindowBackendScaleFactorChanged, WindowFocused, WindowResized,
+    WindowScaleFactorChanged,
+};
 use window::{Win32WindowHandle, Win32Windows};
 use windows::Win32::{Foundation::*, UI::WindowsAndMessaging::*};
 
+pub mod clipboard;
 mod converters;
+pub mod gamepad;
 mod system;
 pub mod window;
 
+pub use system::{apply_on_window_thread, WindowThreadState};
+
 #[derive(Debug, thiserror::Error)]
 pub enum BevyWin32Error {
     #[error("Window {0:?} not associated to an entity")]
     UnassociatedWindow(HWND),
+
+    #[error("Windows error {0}")]
+    Win(#[from] windows::core::Error),
 }
 
 #[derive(SystemParam)]
 struct WindowAndInputEventWriters<'w> {
+    window_focused: EventWriter<'w, WindowFocused>,
+    window_resized: EventWriter<'w, WindowResized>,
+    window_scale_factor_changed: EventWriter<'w, WindowScaleFactorChanged>,
+    window_backend_scale_factor_changed: EventWriter<'w, WindowBackendScaleFactorChanged>,
     keyboard_input: EventWriter<'w, KeyboardInput>,
     character_input: EventWriter<'w, ReceivedCharacter>,
     mouse_button_input: EventWriter<'w, MouseButtonInput>,
     mouse_wheel_input: EventWriter<'w, MouseWheel>,
-    // cursor_moved: EventWriter<'w, CursorMoved>,
+    cursor_moved: EventWriter<'w, CursorMoved>,
     // cursor_entered: EventWriter<'w, CursorEntered>,
     // cursor_left: EventWriter<'w, CursorLeft>,
     mouse_motion: EventWriter<'w, MouseMotion>,
+    input: EventWriter<'w, Win32InputEvent>,
+}
+
+impl WindowAndInputEventWriters<'_> {
+    fn send_keyboard_input(&mut self, event: KeyboardInput) {
+        self.input.send(Win32InputEvent::Keyboard(event));
+        self.keyboard_input.send(event);
+    }
+
+    fn send_character_input(&mut self, event: ReceivedCharacter) {
+        self.input.send(Win32InputEvent::Character(event.clone()));
+        self.character_input.send(event);
+    }
+
+    fn send_mouse_button_input(&mut self, event: MouseButtonInput) {
+        self.input.send(Win32InputEvent::MouseButton(event));
+        self.mouse_button_input.send(event);
+    }
+
+    fn send_mouse_wheel_input(&mut self, event: MouseWheel) {
+        self.input.send(Win32InputEvent::MouseWheel(event));
+        self.mouse_wheel_input.send(event);
+    }
+
+    fn send_cursor_moved(&mut self, event: CursorMoved) {
+        self.input.send(Win32InputEvent::CursorMoved(event.clone()));
+        self.cursor_moved.send(event);
+    }
 }
 
+/// The window input events, in the order the messages for them arrived.
+///
+/// Each of these is also sent as its own bevy event, but readers of different event types can't
+/// tell which came first, e.g. whether a key was released before or after a character was typed.
+#[derive(Debug, Clone, Event)]
+pub enum Win32InputEvent {
+    Keyboard(KeyboardInput),
+    Character(ReceivedCharacter),
+    CursorMoved(CursorMoved),
+    MouseButton(MouseButtonInput),
+    MouseWheel(MouseWheel),
+}
+
+impl Win32InputEvent {
+    /// The window that received the input.
+    pub fn window(&self) -> Entity {
+        match self {
+            Win32InputEvent::Keyboard(event) => event.window,
+            Win32InputEvent::Character(event) => event.window,
+            Win32InputEvent::CursorMoved(event) => event.window,
+            Win32InputEvent::MouseButton(event) => event.window,
+            Win32InputEvent::MouseWheel(event) => event.window,
+        }
+    }
+}
+
+/// A window message, buffered until the [`Win32Plugin`] turns it into bevy events.
+///
+/// Messages that pass a pointer in `lparam` have what it points to copied, since the pointer is
+/// only valid while the message is being handled.
 #[derive(Debug, Event)]
 pub struct WinMessageEvent {
     pub window: Entity,
     pub msg: u32,
     pub wparam: WPARAM,
     pub lparam: LPARAM,
+    /// The window rect suggested by `WM_DPICHANGED`.
+    pub suggested_rect: Option<RECT>,
+}
+
+impl WinMessageEvent {
+    /// Copies the message and anything its `lparam` points to.
+    ///
+    /// # Safety
+    /// `msg` has to be a message that is still being handled, so its pointers are valid.
+    pub unsafe fn from_msg(window: Entity, msg: &MSG) -> Self {
+        let suggested_rect = match msg.message {
+            WM_DPICHANGED => Some(*(msg.lParam.0 as *const RECT)),
+            _ => None,
+        };
+        Self {
+            window,
+            msg: msg.message,
+            wparam: msg.wParam,
+            lparam: msg.lParam,
+            suggested_rect,
+        }
+    }
+}
+
+/// Moves and sizes the window to `rect`, as `WM_DPICHANGED` asks of it.
+///
+/// The window belongs to another thread, so the request is posted to it instead of waiting for it
+/// to be handled.
+fn set_window_rect(hwnd: HWND, rect: RECT) {
+    let result = unsafe {
+        SetWindowPos(
+            hwnd,
+            None,
+            rect.left,
+            rect.top,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            SWP_NOZORDER | SWP_NOACTIVATE | SWP_ASYNCWINDOWPOS,
+        )
+    };
+    if let Err(err) = result {
+        log::warn!("failed to move window {hwnd:?} to its DPI suggested rect: {err}");
+    }
 }
 
 fn process_message(
     mut event_writers: WindowAndInputEventWriters,
     mut wm_event: EventReader<WinMessageEvent>,
+    mut windows: Query<&mut Window>,
+    win32_windows: Res<Win32Windows>,
 ) {
     for event in wm_event.iter() {
         let (window, msg, wparam, lparam) = (event.window, event.msg, event.wparam, event.lparam);
         // TODO: IME Support & window support & some other stuff.
         match msg {
-            WM_MOUSEMOVE => event_writers.mouse_motion.send(MouseMotion {
-                // TODO: This is wrong, we get delta by taking new - old
-                delta: converters::mouse_pos_from_lparam(lparam),
-            }),
+            WM_MOUSEMOVE => {
+                let physical_position = converters::mouse_pos_from_lparam(lparam);
+                if let Ok(mut bevy_window) = windows.get_mut(window) {
+                    bevy_window.set_physical_cursor_position(Some(physical_position.as_dvec2()));
+                    event_writers.send_cursor_moved(CursorMoved {
+                        window,
+                        position: physical_position / bevy_window.scale_factor() as f32,
+                    });
+                }
+                event_writers.mouse_motion.send(MouseMotion {
+                    // TODO: This is wrong, we get delta by taking new - old
+                    delta: physical_position,
+                })
+            }
             WM_LBUTTONDOWN | WM_LBUTTONDBLCLK => {
-                event_writers.mouse_button_input.send(MouseButtonInput {
+                event_writers.send_mouse_button_input(MouseButtonInput {
                     button: MouseButton::Left,
                     state: ButtonState::Pressed,
                     window,
                 })
             }
-            WM_LBUTTONUP => event_writers.mouse_button_input.send(MouseButtonInput {
+            WM_LBUTTONUP => event_writers.send_mouse_button_input(MouseButtonInput {
                 button: MouseButton::Left,
                 state: ButtonState::Released,
                 window,
             }),
             WM_RBUTTONDOWN | WM_RBUTTONDBLCLK => {
-                event_writers.mouse_button_input.send(MouseButtonInput {
+                event_writers.send_mouse_button_input(MouseButtonInput {
                     button: MouseButton::Right,
                     state: ButtonState::Pressed,
                     window,
                 })
             }
-            WM_RBUTTONUP => event_writers.mouse_button_input.send(MouseButtonInput {
+            WM_RBUTTONUP => event_writers.send_mouse_button_input(MouseButtonInput {
                 button: MouseButton::Right,
                 state: ButtonState::Released,
                 window,
             }),
             WM_MBUTTONDOWN | WM_MBUTTONDBLCLK => {
-                event_writers.mouse_button_input.send(MouseButtonInput {
+                event_writers.send_mouse_button_input(MouseButtonInput {
                     button: MouseButton::Middle,
                     state: ButtonState::Pressed,
                     window,
                 })
             }
-            WM_MBUTTONUP => event_writers.mouse_button_input.send(MouseButtonInput {
+            WM_MBUTTONUP => event_writers.send_mouse_button_input(MouseButtonInput {
                 button: MouseButton::Middle,
                 state: ButtonState::Released,
                 window,
             }),
             WM_XBUTTONDOWN | WM_XBUTTONDBLCLK => {
-                event_writers.mouse_button_input.send(MouseButtonInput {
-                    button: MouseButton::Other(wparam.0 as u16), // TODO: Make sure wparam
+                event_writers.send_mouse_button_input(MouseButtonInput {
+                    button: MouseButton::Other(converters::xbutton_from_wparam(wparam)),
                     state: ButtonState::Pressed,
                     window,
                 })
             }
-            WM_XBUTTONUP => event_writers.mouse_button_input.send(MouseButtonInput {
-                button: MouseButton::Other(wparam.0 as u16), // TODO: Make sure wparam
-                state: ButtonState::Pressed,
+            WM_XBUTTONUP => event_writers.send_mouse_button_input(MouseButtonInput {
+                button: MouseButton::Other(converters::xbutton_from_wparam(wparam)),
+                state: ButtonState::Released,
                 window,
             }),
             WM_CHAR => {
                 if let Some(char) = char::from_u32(wparam.0 as _) {
                     // TODO: Should we check this?
                     if !char.is_control() {
-                        event_writers
-                            .character_input
-                            .send(ReceivedCharacter { window, char })
+                        event_writers.send_character_input(ReceivedCharacter { window, char })
                     }
                 }
             }
-            WM_MOUSEWHEEL => event_writers.mouse_wheel_input.send(MouseWheel {
+            WM_MOUSEWHEEL => event_writers.send_mouse_wheel_input(MouseWheel {
                 unit: MouseScrollUnit::Pixel,
                 x: 0.0,
                 y: converters::mouse_wheel_delta_from_wparam(wparam),
                 window,
             }),
-            WM_MOUSEHWHEEL => event_writers.mouse_wheel_input.send(MouseWheel {
+            WM_MOUSEHWHEEL => event_writers.send_mouse_wheel_input(MouseWheel {
                 unit: MouseScrollUnit::Pixel,
                 x: converters::mouse_wheel_delta_from_wparam(wparam),
                 y: 0.0,
                 window,
             }),
-            WM_KEYDOWN | WM_SYSKEYDOWN => event_writers.keyboard_input.send(KeyboardInput {
+            WM_SETFOCUS | WM_KILLFOCUS => {
+                let focused = msg == WM_SETFOCUS;
+                if let Ok(mut bevy_window) = windows.get_mut(window) {
+                    bevy_window.focused = focused;
+                }
+                event_writers
+                    .window_focused
+                    .send(WindowFocused { window, focused });
+            }
+            WM_SIZE => {
+                let Ok(mut bevy_window) = windows.get_mut(window) else {
+                    continue;
+                };
+                let (width, height) = converters::size_from_lparam(lparam);
+                // Minimizing sizes the client area to nothing, keep the last size instead.
+                if width == 0 || height == 0 {
+                    continue;
+                }
+                bevy_window
+                    .resolution
+                    .set_physical_resolution(width, height);
+                event_writers.window_resized.send(WindowResized {
+                    window,
+                    width: bevy_window.width(),
+                    height: bevy_window.height(),
+                });
+            }
+            WM_DPICHANGED => {
+                let Ok(mut bevy_window) = windows.get_mut(window) else {
+                    continue;
+                };
+                let scale_factor = converters::scale_factor_from_dpi_wparam(wparam);
+                bevy_window.resolution.set_scale_factor(scale_factor);
+                // The suggested rect is the outer window rect, the window is moved to it and the
+                // client size it ends up with comes in the following `WM_SIZE`.
+                if let (Some(rect), Some(handle)) =
+                    (event.suggested_rect, win32_windows.get_window(window))
+                {
+                    set_window_rect(handle.handle(), rect);
+                }
+
+                event_writers.window_backend_scale_factor_changed.send(
+                    WindowBackendScaleFactorChanged {
+                        window,
+                        scale_factor,
+                    },
+                );
+                if bevy_window.resolution.scale_factor_override().is_none() {
+                    event_writers
+                        .window_scale_factor_changed
+                        .send(WindowScaleFactorChanged {
+                            window,
+                            scale_factor,
+                        });
+                }
+            }
+            WM_KEYDOWN | WM_SYSKEYDOWN => event_writers.send_keyboard_input(KeyboardInput {
                 scan_code: converters::scancode_from_lparam(lparam).0,
                 key_code: converters::keycode_from_wparam(wparam),
                 state: ButtonState::Pressed,
                 window,
             }),
-            WM_KEYUP | WM_SYSKEYUP => event_writers.keyboard_input.send(KeyboardInput {
+            WM_KEYUP | WM_SYSKEYUP => event_writers.send_keyboard_input(KeyboardInput {
                 scan_code: converters::scancode_from_lparam(lparam).0,
                 key_code: converters::keycode_from_wparam(wparam),
                 state: ButtonState::Released,
@@ -154,7 +335,10 @@ fn add_windows(
     for ev in aw_event.iter() {
         // TODO: Actually get window
         // let window = Window::from(ev.handle);
-        let window = Window::default();
+        let window = Window {
+            resolution: ev.handle.resolution(),
+            ..Default::default()
+        };
         let window_title = window.title.clone();
         log::info!(
             "adding window ({}) with handle ({:?})",
@@ -174,8 +358,12 @@ pub struct Win32Plugin;
 impl Plugin for Win32Plugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Win32Windows>()
+            .init_resource::<gamepad::Win32Gamepads>()
+            .init_resource::<clipboard::Clipboard>()
             .add_event::<AddWindowEvent>()
             .add_event::<WinMessageEvent>()
+            .add_event::<Win32InputEvent>()
+            .add_systems(PreUpdate, gamepad::poll_gamepads.before(InputSystem))
             .add_systems(Update, (add_windows, process_message));
     }
 }
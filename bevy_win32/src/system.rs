@@ -1,10 +1,22 @@
 // TODO: Propagate window info to a bevy `Window` (so we can get window position, size, title, etc...).
 
 use bevy_ecs::prelude::*;
+use bevy_math::Vec2;
 use bevy_window::{prelude::*, RawHandleWrapper, WindowCreated};
-use windows::Win32::Foundation::HWND;
+use windows::Win32::{
+    Foundation::{HWND, POINT},
+    UI::{
+        Input::Ime::{
+            ImmGetContext, ImmReleaseContext, ImmSetCompositionWindow, CFS_POINT, COMPOSITIONFORM,
+        },
+        WindowsAndMessaging::{LoadCursorW, SetCursor, HCURSOR, HTCLIENT, MSG, WM_SETCURSOR},
+    },
+};
 
-use crate::window::{Win32WindowHandle, Win32Windows};
+use crate::{
+    converters,
+    window::{Win32WindowHandle, Win32Windows},
+};
 
 // TODO: We dont need this now...
 pub fn create_windows(
@@ -32,3 +44,71 @@ pub fn create_windows(
         // win32_windows.associate_handle_with_entity(entity, window_handle)
     }
 }
+
+/// What [`apply_on_window_thread`] last applied to a `win32` window.
+#[derive(Debug, Default, Clone)]
+pub struct WindowThreadState {
+    /// The IME position in physical pixels, `None` while the IME is disabled.
+    ime_position: Option<Vec2>,
+}
+
+/// Applies the cursor and the IME position of `window` to its `win32` window, while `msg` is
+/// being handled.
+///
+/// Both belong to the thread of the window, so this has to be called from a hook on its messages
+/// rather than from the schedule. `WM_SETCURSOR` over the client area is answered with the cursor
+/// of `window` when `set_cursor` is set, returning `true` as the message has to be kept from the
+/// window for it not to set its own cursor.
+pub fn apply_on_window_thread(
+    msg: &MSG,
+    window: &Window,
+    state: &mut WindowThreadState,
+    set_cursor: bool,
+) -> bool {
+    let ime_position = window
+        .ime_enabled
+        .then(|| window.ime_position * window.scale_factor() as f32);
+    if ime_position != state.ime_position {
+        state.ime_position = ime_position;
+        if let Some(position) = ime_position {
+            set_ime_position(msg.hwnd, position);
+        }
+    }
+
+    let client_area = (msg.lParam.0 & 0xFFFF) as u32 == HTCLIENT;
+    if msg.message != WM_SETCURSOR || !client_area || !set_cursor {
+        return false;
+    }
+    self::set_cursor(window.cursor.icon, window.cursor.visible);
+    true
+}
+
+fn set_cursor(icon: CursorIcon, visible: bool) {
+    unsafe {
+        let cursor = match visible {
+            true => LoadCursorW(None, converters::cursor_name_from_icon(icon)).unwrap_or_default(),
+            false => HCURSOR::default(),
+        };
+        SetCursor(cursor);
+    }
+}
+
+/// Moves the IME composition window to `position`, in physical pixels relative to the window.
+fn set_ime_position(hwnd: HWND, position: Vec2) {
+    unsafe {
+        let himc = ImmGetContext(hwnd);
+        if himc.is_invalid() {
+            return;
+        }
+        let form = COMPOSITIONFORM {
+            dwStyle: CFS_POINT,
+            ptCurrentPos: POINT {
+                x: position.x as i32,
+                y: position.y as i32,
+            },
+            ..Default::default()
+        };
+        ImmSetCompositionWindow(himc, &form);
+        ImmReleaseContext(hwnd, himc);
+    }
+}
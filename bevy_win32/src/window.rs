@@ -12,7 +12,12 @@ use bevy_window::{
 };
 use windows::Win32::{
     Foundation::*,
-    UI::WindowsAndMessaging::{GetWindowRect, GetWindowTextLengthW, GetWindowTextW},
+    UI::{
+        HiDpi::GetDpiForWindow,
+        WindowsAndMessaging::{
+            GetWindowRect, GetWindowTextLengthW, GetWindowTextW, USER_DEFAULT_SCREEN_DPI,
+        },
+    },
 };
 
 // TODO: Propagate window info to a bevy `Window` (so we can get window position, size, title, etc...).
@@ -52,17 +57,29 @@ impl Win32WindowHandle {
     }
 
     // TODO: https://learn.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-windowinfo
-    // TODO: Add scaling: `with_scale_factor_override`
     pub fn resolution(&self) -> WindowResolution {
         let mut rect = RECT::default();
         unsafe {
             // TODO: Error handling.
             GetWindowRect(self.0, &mut rect).unwrap();
         }
-        WindowResolution::new(
-            (rect.right - rect.left) as f32,
-            (rect.bottom - rect.top) as f32,
-        )
+        let mut resolution = WindowResolution::default();
+        resolution.set_scale_factor(self.scale_factor());
+        resolution.set_physical_resolution(
+            (rect.right - rect.left) as u32,
+            (rect.bottom - rect.top) as u32,
+        );
+        resolution
+    }
+
+    /// The ratio of physical pixels to logical pixels, as given by the windows DPI.
+    ///
+    /// NOTE: Unless the game is per-monitor DPI aware this will always be `1.0`.
+    pub fn scale_factor(&self) -> f64 {
+        match unsafe { GetDpiForWindow(self.0) } {
+            0 => 1.0,
+            dpi => dpi as f64 / USER_DEFAULT_SCREEN_DPI as f64,
+        }
     }
 
     pub fn title(&self) -> String {
@@ -6,7 +6,7 @@ use mesh::{GpuMesh, GpuVertex};
 use shaders::CompiledShaders;
 use texture::TextureAllocator;
 use windows::{
-    core::{HRESULT, PCSTR},
+    core::PCSTR,
     Win32::{
         Foundation::RECT,
         Graphics::{
@@ -72,7 +72,41 @@ pub struct DirectX11Renderer {
     tex_alloc: TextureAllocator,
     input_layout: ID3D11InputLayout,
     shaders: CompiledShaders,
-    pub backup: BackupState,
+}
+
+/// The state of the game's device context, saved while the overlay paints and restored on drop.
+///
+/// Keeping one alive around every [`DirectX11Renderer::paint_batch`] of a frame saves and
+/// restores the state once, instead of once per batch.
+pub struct DeviceStateGuard {
+    context: ID3D11DeviceContext,
+    backup: BackupState,
+}
+
+impl DeviceStateGuard {
+    /// Saves the state of the immediate context of the swapchain's device.
+    ///
+    /// # Safety
+    /// `swap_chain` has to be a live swapchain.
+    pub unsafe fn save(swap_chain: &IDXGISwapChain) -> Result<Self, DirectX11Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let device: ID3D11Device = swap_chain.GetDevice()?;
+        let context = device.GetImmediateContext()?;
+        let mut backup = BackupState::default();
+        backup.save(&context);
+        Ok(Self { context, backup })
+    }
+}
+
+impl Drop for DeviceStateGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_scope!("restore_device_state");
+
+        self.backup.restore(&self.context);
+    }
 }
 
 impl DirectX11Renderer {
@@ -99,42 +133,88 @@ impl DirectX11Renderer {
 
         Ok(Self {
             tex_alloc: TextureAllocator::default(),
-            backup: BackupState::default(),
             input_layout,
             render_view,
             shaders,
         })
     }
 
-    /// Paints the primitives to the swapchain.
+    /// The size of the back buffers of the swapchain in pixels.
     ///
-    /// NOTE: This should be called _ONCE_ per frame.
-    #[allow(clippy::cast_ref_to_mut)]
+    /// # Safety
+    /// `swap_chain` has to be a live swapchain.
+    pub unsafe fn target_size(swap_chain: &IDXGISwapChain) -> Result<(f32, f32), DirectX11Error> {
+        let mut swap_chain_desc = DXGI_SWAP_CHAIN_DESC::default();
+        swap_chain.GetDesc(&mut swap_chain_desc)?;
+        Ok((
+            swap_chain_desc.BufferDesc.Width as f32,
+            swap_chain_desc.BufferDesc.Height as f32,
+        ))
+    }
+
+    /// Paints the primitives to the swapchain, saving and restoring the state of the game around
+    /// it.
+    ///
+    /// The primitives are in points, `pixels_per_point` scales them to the size of the swapchain
+    /// in pixels, which is read every call so it follows resizes.
+    ///
+    /// NOTE: This should be called _ONCE_ per frame, see [`DirectX11Renderer::paint_batch`] for
+    /// painting more than once.
     pub unsafe fn paint_primitives(
         &mut self,
-        // TODO: Add window size as param?
         swap_chain: &IDXGISwapChain,
-        target_size: (f32, f32),
+        pixels_per_point: f32,
         textures_delta: TexturesDelta,
         primitives: Vec<ClippedPrimitive>,
     ) -> Result<(), DirectX11Error> {
-        #[cfg(feature = "puffin")]
-        puffin::profile_function!();
+        let _state = DeviceStateGuard::save(swap_chain)?;
+        self.update_textures(swap_chain, textures_delta)?;
+        let primitives: Vec<&ClippedPrimitive> = primitives.iter().collect();
+        self.paint_batch(swap_chain, pixels_per_point, &primitives)
+    }
 
+    /// Uploads and frees the textures of the delta, before painting primitives that use them.
+    ///
+    /// # Safety
+    /// `swap_chain` has to be the live swapchain the renderer was created from.
+    pub unsafe fn update_textures(
+        &mut self,
+        swap_chain: &IDXGISwapChain,
+        textures_delta: TexturesDelta,
+    ) -> Result<(), DirectX11Error> {
+        if textures_delta.is_empty() {
+            return Ok(());
+        }
         let device: ID3D11Device = swap_chain.GetDevice()?;
         let context = device.GetImmediateContext()?;
-        self.backup.save(&context);
+        self.tex_alloc
+            .process_deltas(&device, &context, textures_delta)
+    }
 
-        if !textures_delta.is_empty() {
-            self.tex_alloc
-                .process_deltas(&device, &context, textures_delta)?;
-        }
+    /// Paints the primitives to the swapchain like [`DirectX11Renderer::paint_primitives`], but
+    /// leaves the state of the device context as it is.
+    ///
+    /// # Safety
+    /// `swap_chain` has to be the live swapchain the renderer was created from, and a
+    /// [`DeviceStateGuard`] has to be alive to give the game its state back.
+    #[allow(clippy::cast_ref_to_mut)]
+    pub unsafe fn paint_batch(
+        &mut self,
+        swap_chain: &IDXGISwapChain,
+        pixels_per_point: f32,
+        primitives: &[&ClippedPrimitive],
+    ) -> Result<(), DirectX11Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
 
-        if primitives.is_empty() {
-            self.backup.restore(&context);
+        if primitives.is_empty() || self.render_view.is_none() {
             return Ok(());
         }
 
+        let target_size = Self::target_size(swap_chain)?;
+        let device: ID3D11Device = swap_chain.GetDevice()?;
+        let context = device.GetImmediateContext()?;
+
         self.set_blend_state(&device, &context)?;
         //self.set_depth_stencil_state(&device, &context)?;
         self.set_raster_options(&device, &context)?;
@@ -142,9 +222,11 @@ impl DirectX11Renderer {
 
         // `ClippedPrimitive` -> `GpuMesh`
         let meshes = primitives
-            .into_iter()
-            .filter_map(|clip_prim| match clip_prim.primitive {
-                Primitive::Mesh(mesh) => GpuMesh::from_mesh(target_size, mesh, clip_prim.clip_rect),
+            .iter()
+            .filter_map(|clip_prim| match &clip_prim.primitive {
+                Primitive::Mesh(mesh) => {
+                    GpuMesh::from_mesh(target_size, pixels_per_point, mesh, clip_prim.clip_rect)
+                }
                 Primitive::Callback(_) => {
                     panic!("custom rendering callbacks are not implemented")
                 }
@@ -194,28 +276,29 @@ impl DirectX11Renderer {
             context.DrawIndexed(mesh.indices.len() as _, 0, 0);
         }
 
-        self.backup.restore(&context);
-
         Ok(())
     }
 
-    // TODO: Fix this so we dont need to know about original.
-    /// Call when resizing buffers.
-    /// Do not call the original function before it, instead call it inside of the `original` closure.
-    /// # Behavior
-    /// In `origin` closure make sure to call the original `ResizeBuffers`.
-    pub unsafe fn on_resize_buffers(
-        &mut self,
-        swap_chain: IDXGISwapChain,
-        original: impl FnOnce() -> HRESULT,
-    ) -> Result<HRESULT, DirectX11Error> {
-        // TODO: This fucking sucks.
+    /// Releases the render target view on the back buffer, which has to happen before
+    /// `ResizeBuffers` as it fails while the buffers are referenced.
+    ///
+    /// Nothing is painted until [`DirectX11Renderer::recreate_render_target`] is called.
+    pub fn release_render_target(&mut self) {
         drop(self.render_view.take());
-        let result = original();
+    }
+
+    /// Creates the render target view on the new back buffer, after `ResizeBuffers`.
+    ///
+    /// # Safety
+    /// `swap_chain` has to be the live swapchain the renderer was created from.
+    pub unsafe fn recreate_render_target(
+        &mut self,
+        swap_chain: &IDXGISwapChain,
+    ) -> Result<(), DirectX11Error> {
         let backbuffer: ID3D11Texture2D = swap_chain.GetBuffer(0)?;
         let device: ID3D11Device = swap_chain.GetDevice()?;
         device.CreateRenderTargetView(&backbuffer, None, Some(&mut self.render_view))?;
-        Ok(result)
+        Ok(())
     }
 
     fn set_blend_state(
@@ -15,33 +15,55 @@ pub struct GpuMesh {
 }
 
 impl GpuMesh {
-    pub fn from_mesh((w, h): (f32, f32), mesh: Mesh, scissors: Rect) -> Option<Self> {
+    /// Converts an egui mesh (in points) into a mesh in normalized device coordinates.
+    ///
+    /// The `scissors` are scaled by `pixels_per_point` so that they are in the same pixel space as `(w, h)`.
+    pub fn from_mesh(
+        (w, h): (f32, f32),
+        pixels_per_point: f32,
+        mesh: &Mesh,
+        scissors: Rect,
+    ) -> Option<Self> {
         if mesh.indices.is_empty() || mesh.indices.len() % 3 != 0 {
             None
         } else {
             let vertices = mesh
                 .vertices
-                .into_iter()
-                .map(|v| GpuVertex {
-                    pos: Pos2::new(
-                        (v.pos.x - w / 2.) / (w / 2.),
-                        (v.pos.y - h / 2.) / -(h / 2.),
-                    ),
-                    uv: v.uv,
-                    color: v.color.into(),
+                .iter()
+                .map(|v| {
+                    let (x, y) = (v.pos.x * pixels_per_point, v.pos.y * pixels_per_point);
+                    GpuVertex {
+                        pos: Pos2::new((x - w / 2.) / (w / 2.), (y - h / 2.) / -(h / 2.)),
+                        uv: v.uv,
+                        color: v.color.into(),
+                    }
                 })
                 .collect();
 
             Some(Self {
                 texture_id: mesh.texture_id,
-                indices: mesh.indices,
-                clip: scissors,
+                indices: mesh.indices.clone(),
+                clip: scale_clip_rect((w, h), pixels_per_point, scissors),
                 vertices,
             })
         }
     }
 }
 
+/// Scales a clip rect from points to pixels, rounding outwards and clamping it to the target.
+fn scale_clip_rect((w, h): (f32, f32), pixels_per_point: f32, clip: Rect) -> Rect {
+    Rect::from_min_max(
+        Pos2::new(
+            (clip.min.x * pixels_per_point).floor().clamp(0., w),
+            (clip.min.y * pixels_per_point).floor().clamp(0., h),
+        ),
+        Pos2::new(
+            (clip.max.x * pixels_per_point).ceil().clamp(0., w),
+            (clip.max.y * pixels_per_point).ceil().clamp(0., h),
+        ),
+    )
+}
+
 #[repr(C)]
 pub struct GpuVertex {
     pos: Pos2,
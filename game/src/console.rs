@@ -0,0 +1,471 @@
+//! An in-overlay developer console.
+//!
+//! The scrollback shows the log along with the commands that were run. Commands are registered
+//! with [`ConsoleAppExt::register_command`] and run as systems taking their typed arguments with
+//! [`In`], e.g.
+//!
+//! ```ignore
+//! app.register_command("profiler", "Turns profiling on or off", |In((on,)): In<(bool,)>| {
+//!     puffin::set_scopes_on(on);
+//! });
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy_app::prelude::*;
+use bevy_ecs::{prelude::*, system::BoxedSystem};
+
+use crate::{
+    dock::{DockAppExt, DockTab},
+    logger::{viewer, LogRecord, LogRecords},
+};
+
+use self::parse::{ArgSpec, CommandArgs, Completion, ParseError};
+
+pub mod parse;
+
+/// How many lines the scrollback keeps.
+const SCROLLBACK_CAPACITY: usize = 1000;
+
+/// How many submitted lines the history keeps.
+const HISTORY_CAPACITY: usize = 100;
+
+/// Runs a command with its tokens after the name.
+trait CommandHandler: Send + Sync {
+    fn run(&mut self, tokens: &[String], world: &mut World) -> Result<(), ParseError>;
+}
+
+/// A command handled by a system, which is initialized the first time the command runs.
+struct SystemCommandHandler<A: CommandArgs> {
+    system: BoxedSystem<A, ()>,
+    initialized: bool,
+}
+
+impl<A: CommandArgs> CommandHandler for SystemCommandHandler<A> {
+    fn run(&mut self, tokens: &[String], world: &mut World) -> Result<(), ParseError> {
+        let args = A::parse(tokens)?;
+        if !self.initialized {
+            self.system.initialize(world);
+            self.initialized = true;
+        }
+        self.system.run(args, world);
+        self.system.apply_deferred(world);
+        Ok(())
+    }
+}
+
+pub struct ConsoleCommand {
+    help: String,
+    args: Vec<ArgSpec>,
+    /// `None` for the commands built into the console itself.
+    handler: Option<Box<dyn CommandHandler>>,
+}
+
+/// The registered commands, by name.
+#[derive(Default, Resource)]
+pub struct ConsoleCommands {
+    commands: BTreeMap<String, ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    fn insert(&mut self, name: &str, command: ConsoleCommand) {
+        if self.commands.insert(name.to_owned(), command).is_some() {
+            log::warn!("console command {} was registered twice", name);
+        }
+    }
+
+    pub fn usage(&self, name: &str) -> Option<String> {
+        let command = self.commands.get(name)?;
+        Some(parse::usage(name, &command.args))
+    }
+
+    pub fn complete(&self, line: &str) -> Completion {
+        Completion::new(line, self.commands.keys().map(String::as_str), |name| {
+            self.commands
+                .get(name)
+                .map(|command| command.args.as_slice())
+        })
+    }
+}
+
+pub trait ConsoleAppExt {
+    /// Registers a console command, which runs `handler` with the parsed arguments.
+    fn register_command<A: CommandArgs, M>(
+        &mut self,
+        name: &str,
+        help: &str,
+        handler: impl IntoSystem<A, (), M>,
+    ) -> &mut Self;
+}
+
+impl ConsoleAppExt for App {
+    fn register_command<A: CommandArgs, M>(
+        &mut self,
+        name: &str,
+        help: &str,
+        handler: impl IntoSystem<A, (), M>,
+    ) -> &mut Self {
+        let command = ConsoleCommand {
+            help: help.to_owned(),
+            args: A::specs(),
+            handler: Some(Box::new(SystemCommandHandler {
+                system: Box::new(IntoSystem::into_system(handler)),
+                initialized: false,
+            })),
+        };
+        self.world
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .insert(name, command);
+        self
+    }
+}
+
+/// The lines submitted to the console, newest last.
+#[derive(Debug, Default)]
+pub struct ConsoleHistory {
+    entries: VecDeque<String>,
+    /// The entry being shown while stepping through the history.
+    browsing: Option<usize>,
+}
+
+impl ConsoleHistory {
+    pub fn push(&mut self, line: &str) {
+        self.browsing = None;
+        if line.is_empty() || self.entries.back().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.entries.len() == HISTORY_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(line.to_owned());
+    }
+
+    /// Steps to the next older entry, staying on the oldest one.
+    pub fn previous(&mut self) -> Option<&str> {
+        let index = match self.browsing {
+            Some(index) => index.saturating_sub(1),
+            None => self.entries.len().checked_sub(1)?,
+        };
+        self.browsing = Some(index);
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Steps to the next newer entry, giving an empty line after the newest one.
+    ///
+    /// `None` if we are not stepping through the history.
+    pub fn next(&mut self) -> Option<&str> {
+        let index = self.browsing? + 1;
+        if index >= self.entries.len() {
+            self.browsing = None;
+            return Some("");
+        }
+        self.browsing = Some(index);
+        self.entries.get(index).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ConsoleLine {
+    Log(LogRecord),
+    /// A line that was submitted.
+    Input(String),
+    Output(String),
+    Error(String),
+}
+
+#[derive(Default, Resource)]
+pub struct Console {
+    scrollback: VecDeque<ConsoleLine>,
+    history: ConsoleHistory,
+    input: String,
+    /// Submitted lines waiting for [`run_console_commands`].
+    pending: Vec<String>,
+    /// The index of the next log record to add to the scrollback.
+    next_log_record: u64,
+}
+
+impl Console {
+    fn push_line(&mut self, line: ConsoleLine) {
+        if self.scrollback.len() == SCROLLBACK_CAPACITY {
+            self.scrollback.pop_front();
+        }
+        self.scrollback.push_back(line);
+    }
+
+    pub fn print(&mut self, text: impl Into<String>) {
+        self.push_line(ConsoleLine::Output(text.into()));
+    }
+
+    pub fn print_error(&mut self, text: impl Into<String>) {
+        self.push_line(ConsoleLine::Error(text.into()));
+    }
+
+    pub fn clear(&mut self) {
+        self.scrollback.clear();
+    }
+
+    /// Queues a line to be run as if the user typed it.
+    pub fn submit(&mut self, line: &str) {
+        self.history.push(line);
+        self.pending.push(line.to_owned());
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, commands: &ConsoleCommands) {
+        let input_id = ui.id().with("console_input");
+        let has_focus = ui.memory(|memory| memory.has_focus(input_id));
+        // Take the keys before the text edit moves its cursor with them.
+        let (tab, up, down) = ui.input_mut(|input| {
+            let mut consume = |key| has_focus && input.consume_key(egui::Modifiers::NONE, key);
+            (
+                consume(egui::Key::Tab),
+                consume(egui::Key::ArrowUp),
+                consume(egui::Key::ArrowDown),
+            )
+        });
+
+        let mut input_changed = false;
+        if tab {
+            let completion = commands.complete(&self.input);
+            if let Some(completed) = completion.apply(&self.input) {
+                self.input = completed;
+                input_changed = true;
+            } else if completion.candidates.len() > 1 {
+                self.print(completion.candidates.join("  "));
+            }
+        }
+        let browsed = match (up, down) {
+            (true, _) => self.history.previous(),
+            (_, true) => self.history.next(),
+            _ => None,
+        };
+        if let Some(line) = browsed {
+            self.input = line.to_owned();
+            input_changed = true;
+        }
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.input)
+                    .id(input_id)
+                    .lock_focus(true)
+                    .font(egui::TextStyle::Monospace)
+                    .hint_text("help")
+                    .desired_width(f32::INFINITY),
+            );
+            if input_changed {
+                // Put the cursor after what we filled in.
+                if let Some(mut state) = egui::text_edit::TextEditState::load(ui.ctx(), input_id) {
+                    let end = egui::text::CCursor::new(self.input.chars().count());
+                    state.set_ccursor_range(Some(egui::text::CCursorRange::one(end)));
+                    state.store(ui.ctx(), input_id);
+                }
+            }
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                let line = std::mem::take(&mut self.input);
+                if !line.trim().is_empty() {
+                    self.submit(line.trim());
+                }
+                response.request_focus();
+            }
+
+            ui.separator();
+            let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+            egui::ScrollArea::vertical()
+                .auto_shrink([false; 2])
+                .stick_to_bottom(true)
+                .show_rows(ui, row_height, self.scrollback.len(), |ui, rows| {
+                    for line in self.scrollback.range(rows) {
+                        ui.add(egui::Label::new(line_text(line)).wrap(false));
+                    }
+                });
+        });
+    }
+}
+
+fn line_text(line: &ConsoleLine) -> egui::RichText {
+    match line {
+        ConsoleLine::Log(record) => viewer::record_text(record),
+        ConsoleLine::Input(line) => egui::RichText::new(format!("> {}", line))
+            .monospace()
+            .strong(),
+        ConsoleLine::Output(text) => egui::RichText::new(text).monospace(),
+        ConsoleLine::Error(text) => egui::RichText::new(text)
+            .monospace()
+            .color(egui::Color32::LIGHT_RED),
+    }
+}
+
+/// Copies the new log records into the scrollback.
+pub fn collect_console_logs(records: Res<LogRecords>, mut console: ResMut<Console>) {
+    let console = console.as_mut();
+    let mut new_records = Vec::new();
+    records.read_since(&mut console.next_log_record, |record| {
+        new_records.push(record.clone())
+    });
+    for record in new_records {
+        console.push_line(ConsoleLine::Log(record));
+    }
+}
+
+/// Runs the lines submitted to the console.
+pub fn run_console_commands(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<Console>().pending);
+    if pending.is_empty() {
+        return;
+    }
+
+    world.resource_scope(|world, mut commands: Mut<ConsoleCommands>| {
+        for line in pending {
+            world
+                .resource_mut::<Console>()
+                .push_line(ConsoleLine::Input(line.clone()));
+            if let Err(err) = run_command(&line, commands.as_mut(), world) {
+                world.resource_mut::<Console>().print_error(err);
+            }
+        }
+    });
+}
+
+fn run_command(
+    line: &str,
+    commands: &mut ConsoleCommands,
+    world: &mut World,
+) -> Result<(), String> {
+    let tokens = parse::tokenize(line).map_err(|err| err.to_string())?;
+    let Some((name, args)) = tokens.split_first() else {
+        return Ok(());
+    };
+    let usage = commands.usage(name);
+    let Some(command) = commands.commands.get_mut(name.as_str()) else {
+        return Err(format!("Unknown command {}, try help", name));
+    };
+
+    if let Some(handler) = &mut command.handler {
+        return handler
+            .run(args, world)
+            .map_err(|err| format!("{}, usage: {}", err, usage.unwrap_or_default()));
+    }
+
+    let mut console = world.resource_mut::<Console>();
+    match name.as_str() {
+        "help" => match args.first() {
+            Some(name) => match commands.commands.get(name.as_str()) {
+                Some(command) => {
+                    console.print(commands.usage(name).unwrap_or_default());
+                    console.print(format!("    {}", command.help));
+                }
+                None => return Err(format!("Unknown command {}", name)),
+            },
+            None => {
+                for (name, command) in commands.commands.iter() {
+                    console.print(format!("{:<24} {}", name, command.help));
+                }
+            }
+        },
+        "clear" => console.clear(),
+        _ => unreachable!("builtin command {} is not handled", name),
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct ConsoleTab;
+
+impl DockTab for ConsoleTab {
+    const ID: &'static str = "console";
+
+    fn title(&self) -> egui::WidgetText {
+        "Console".into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, world: &mut World) {
+        world.resource_scope(|world, mut console: Mut<Console>| {
+            console.ui(ui, world.resource::<ConsoleCommands>());
+        });
+    }
+}
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        let mut commands = app
+            .world
+            .get_resource_or_insert_with(ConsoleCommands::default);
+        commands.insert(
+            "help",
+            ConsoleCommand {
+                help: "Lists the commands, or shows the usage of one".to_owned(),
+                args: <(Option<String>,)>::specs(),
+                handler: None,
+            },
+        );
+        commands.insert(
+            "clear",
+            ConsoleCommand {
+                help: "Clears the scrollback".to_owned(),
+                args: Vec::new(),
+                handler: None,
+            },
+        );
+
+        app.init_resource::<Console>()
+            .add_dock_tab::<ConsoleTab>()
+            .add_systems(Update, (collect_console_logs, run_console_commands));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(lines: &[&str]) -> ConsoleHistory {
+        let mut history = ConsoleHistory::default();
+        for line in lines {
+            history.push(line);
+        }
+        history
+    }
+
+    #[test]
+    fn history_steps_through_entries() {
+        let mut history = history(&["a", "b", "c"]);
+        assert_eq!(history.next(), None);
+        assert_eq!(history.previous(), Some("c"));
+        assert_eq!(history.previous(), Some("b"));
+        assert_eq!(history.previous(), Some("a"));
+        // Stays on the oldest entry.
+        assert_eq!(history.previous(), Some("a"));
+        assert_eq!(history.next(), Some("b"));
+        assert_eq!(history.next(), Some("c"));
+        // Back to an empty line past the newest one.
+        assert_eq!(history.next(), Some(""));
+        assert_eq!(history.next(), None);
+        assert_eq!(ConsoleHistory::default().previous(), None);
+    }
+
+    #[test]
+    fn history_skips_empty_and_repeated_lines() {
+        let mut history = history(&["a", "", "a", "b", "a"]);
+        assert_eq!(history.previous(), Some("a"));
+        assert_eq!(history.previous(), Some("b"));
+        assert_eq!(history.previous(), Some("a"));
+        assert_eq!(history.previous(), Some("a"));
+
+        // Pushing stops stepping through the history.
+        history.push("c");
+        assert_eq!(history.next(), None);
+        assert_eq!(history.previous(), Some("c"));
+    }
+
+    #[test]
+    fn history_keeps_the_newest_entries() {
+        let lines: Vec<String> = (0..HISTORY_CAPACITY + 5).map(|i| i.to_string()).collect();
+        let mut history = history(&lines.iter().map(String::as_str).collect::<Vec<_>>());
+        assert_eq!(history.entries.len(), HISTORY_CAPACITY);
+        for _ in 0..HISTORY_CAPACITY {
+            history.previous();
+        }
+        assert_eq!(history.previous(), Some("5"));
+    }
+}
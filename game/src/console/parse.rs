@@ -0,0 +1,430 @@
+//! Parsing and completion of console input, kept free of the ui and the world.
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    #[error("Unterminated quote")]
+    UnterminatedQuote,
+
+    #[error("Missing argument <{0}>")]
+    MissingArgument(&'static str),
+
+    #[error("Expected <{expected}> but got {got:?}")]
+    InvalidArgument { expected: &'static str, got: String },
+
+    #[error("Too many arguments, expected at most {0}")]
+    TooManyArguments(usize),
+}
+
+/// Splits a line into tokens on whitespace.
+///
+/// Double quotes group a token with whitespace in it, inside of them `\"` and `\\` are escapes.
+pub fn tokenize(line: &str) -> Result<Vec<String>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            return Ok(tokens);
+        };
+
+        let mut token = String::new();
+        if first == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(c @ ('"' | '\\')) => token.push(c),
+                        Some(c) => {
+                            token.push('\\');
+                            token.push(c);
+                        }
+                        None => return Err(ParseError::UnterminatedQuote),
+                    },
+                    Some(c) => token.push(c),
+                    None => return Err(ParseError::UnterminatedQuote),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                token.push(c);
+            }
+        }
+        tokens.push(token);
+    }
+}
+
+/// A single typed argument of a command.
+pub trait CommandArg: Sized {
+    /// Shown in the usage of the command, e.g. `<int>`.
+    const KIND: &'static str;
+
+    fn parse(token: &str) -> Option<Self>;
+
+    /// The values the argument is completed to, if there is a small set of them.
+    fn completions() -> &'static [&'static str] {
+        &[]
+    }
+}
+
+macro_rules! impl_command_arg_from_str {
+    ($kind:literal: $($ty:ty),*) => {
+        $(
+            impl CommandArg for $ty {
+                const KIND: &'static str = $kind;
+
+                fn parse(token: &str) -> Option<Self> {
+                    token.parse().ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_command_arg_from_str!("int": i32, i64, u32, u64, usize);
+impl_command_arg_from_str!("float": f32, f64);
+
+impl CommandArg for bool {
+    const KIND: &'static str = "bool";
+
+    fn parse(token: &str) -> Option<Self> {
+        match token.to_ascii_lowercase().as_str() {
+            "true" | "on" | "1" => Some(true),
+            "false" | "off" | "0" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn completions() -> &'static [&'static str] {
+        &["true", "false"]
+    }
+}
+
+impl CommandArg for String {
+    const KIND: &'static str = "string";
+
+    fn parse(token: &str) -> Option<Self> {
+        Some(token.to_owned())
+    }
+}
+
+/// Describes an argument, for usage and completion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgSpec {
+    pub kind: &'static str,
+    pub optional: bool,
+    pub completions: &'static [&'static str],
+}
+
+/// A [`CommandArg`] that is either required, or optional when wrapped in an [`Option`].
+pub trait CommandParam: Sized {
+    fn spec() -> ArgSpec;
+
+    fn parse(token: Option<&str>) -> Result<Self, ParseError>;
+}
+
+fn parse_arg<T: CommandArg>(token: &str) -> Result<T, ParseError> {
+    T::parse(token).ok_or_else(|| ParseError::InvalidArgument {
+        expected: T::KIND,
+        got: token.to_owned(),
+    })
+}
+
+impl<T: CommandArg> CommandParam for T {
+    fn spec() -> ArgSpec {
+        ArgSpec {
+            kind: T::KIND,
+            optional: false,
+            completions: T::completions(),
+        }
+    }
+
+    fn parse(token: Option<&str>) -> Result<Self, ParseError> {
+        parse_arg(token.ok_or(ParseError::MissingArgument(T::KIND))?)
+    }
+}
+
+impl<T: CommandArg> CommandParam for Option<T> {
+    fn spec() -> ArgSpec {
+        ArgSpec {
+            optional: true,
+            ..T::spec()
+        }
+    }
+
+    fn parse(token: Option<&str>) -> Result<Self, ParseError> {
+        token.map(parse_arg).transpose()
+    }
+}
+
+/// The arguments of a command, a tuple of [`CommandParam`]s.
+pub trait CommandArgs: Sized + Send + 'static {
+    fn specs() -> Vec<ArgSpec>;
+
+    /// Parses the tokens after the command name.
+    fn parse(tokens: &[String]) -> Result<Self, ParseError>;
+}
+
+macro_rules! impl_command_args {
+    ($($param:ident),*) => {
+        impl<$($param: CommandParam + Send + 'static),*> CommandArgs for ($($param,)*) {
+            fn specs() -> Vec<ArgSpec> {
+                vec![$($param::spec()),*]
+            }
+
+            #[allow(unused_mut, unused_variables)]
+            fn parse(tokens: &[String]) -> Result<Self, ParseError> {
+                let count = <[&str]>::len(&[$(stringify!($param)),*]);
+                if tokens.len() > count {
+                    return Err(ParseError::TooManyArguments(count));
+                }
+                let mut tokens = tokens.iter().map(String::as_str);
+                Ok(($($param::parse(tokens.next())?,)*))
+            }
+        }
+    };
+}
+
+impl_command_args!();
+impl_command_args!(A);
+impl_command_args!(A, B);
+impl_command_args!(A, B, C);
+impl_command_args!(A, B, C, D);
+impl_command_args!(A, B, C, D, E);
+impl_command_args!(A, B, C, D, E, F);
+
+/// Formats the usage of a command, e.g. `fov <float> [bool]`.
+pub fn usage(name: &str, specs: &[ArgSpec]) -> String {
+    let mut usage = name.to_owned();
+    for spec in specs {
+        let (open, close) = match spec.optional {
+            true => ('[', ']'),
+            false => ('<', '>'),
+        };
+        usage.push(' ');
+        usage.push(open);
+        usage.push_str(spec.kind);
+        usage.push(close);
+    }
+    usage
+}
+
+/// The candidates for the token being typed at the end of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// Where the completed token starts in the line.
+    pub start: usize,
+    /// Sorted and without duplicates.
+    pub candidates: Vec<String>,
+}
+
+impl Completion {
+    /// Completes the command name or argument at the end of `line`.
+    ///
+    /// `specs` looks up the arguments of a command by name.
+    pub fn new<'a>(
+        line: &str,
+        names: impl IntoIterator<Item = &'a str>,
+        specs: impl FnOnce(&str) -> Option<&'a [ArgSpec]>,
+    ) -> Self {
+        let partial = line.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let start = line.len() - partial.len();
+        let mut previous = line[..start].split_whitespace();
+
+        let mut candidates: Vec<String> = match previous.next() {
+            None => names
+                .into_iter()
+                .filter(|name| name.starts_with(partial))
+                .map(str::to_owned)
+                .collect(),
+            Some(name) => specs(name)
+                .and_then(|specs| specs.get(previous.count()))
+                .map(|spec| spec.completions)
+                .unwrap_or_default()
+                .iter()
+                .filter(|value| value.starts_with(partial))
+                .map(|value| (*value).to_owned())
+                .collect(),
+        };
+        candidates.sort();
+        candidates.dedup();
+        Self { start, candidates }
+    }
+
+    /// The line with the completion applied, `None` if there is nothing to add.
+    ///
+    /// A single candidate is completed in full, several only as far as they agree.
+    pub fn apply(&self, line: &str) -> Option<String> {
+        let (head, partial) = line.split_at(self.start.min(line.len()));
+        let completed = match self.candidates.as_slice() {
+            [] => return None,
+            [candidate] => format!("{} ", candidate),
+            candidates => common_prefix(candidates).to_owned(),
+        };
+        if completed.len() <= partial.len() {
+            return None;
+        }
+        Some(format!("{}{}", head, completed))
+    }
+}
+
+/// The longest prefix all of `strings` share.
+pub fn common_prefix(strings: &[String]) -> &str {
+    let Some((first, rest)) = strings.split_first() else {
+        return "";
+    };
+    let mut len = first.len();
+    for string in rest {
+        len = first
+            .char_indices()
+            .zip(string.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(len.min(string.len()), |((i, _), _)| i.min(len));
+    }
+    &first[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(line: &str) -> Vec<String> {
+        tokenize(line).unwrap()
+    }
+
+    #[test]
+    fn tokenize_splits_on_whitespace() {
+        assert_eq!(tokens("  fov   90\ttrue "), ["fov", "90", "true"]);
+        assert!(tokens("   ").is_empty());
+    }
+
+    #[test]
+    fn tokenize_quotes_and_escapes() {
+        assert_eq!(tokens(r#"say "hello world""#), ["say", "hello world"]);
+        assert_eq!(tokens(r#""" x"#), ["", "x"]);
+        assert_eq!(tokens(r#""a \"b\" \\ c""#), [r#"a "b" \ c"#]);
+        // Anything else after a backslash is kept as it is.
+        assert_eq!(tokens(r#""C:\dir""#), [r"C:\dir"]);
+        // Quotes only group at the start of a token.
+        assert_eq!(tokens(r#"a"b c"#), [r#"a"b"#, "c"]);
+    }
+
+    #[test]
+    fn tokenize_unterminated_quote() {
+        assert_eq!(
+            tokenize(r#"say "hello"#),
+            Err(ParseError::UnterminatedQuote)
+        );
+        assert_eq!(
+            tokenize(r#""trailing \"#),
+            Err(ParseError::UnterminatedQuote)
+        );
+    }
+
+    #[test]
+    fn typed_arguments() {
+        let parse = |line: &str| <(f32, Option<bool>)>::parse(&tokens(line));
+        assert_eq!(parse("90"), Ok((90.0, None)));
+        assert_eq!(parse("90.5 ON"), Ok((90.5, Some(true))));
+        assert_eq!(parse("1 0"), Ok((1.0, Some(false))));
+        assert_eq!(parse(""), Err(ParseError::MissingArgument("float")));
+        assert_eq!(
+            parse("wide"),
+            Err(ParseError::InvalidArgument {
+                expected: "float",
+                got: "wide".to_owned()
+            })
+        );
+        assert_eq!(
+            parse("90 maybe"),
+            Err(ParseError::InvalidArgument {
+                expected: "bool",
+                got: "maybe".to_owned()
+            })
+        );
+        assert_eq!(parse("90 true 1"), Err(ParseError::TooManyArguments(2)));
+        assert_eq!(
+            <()>::parse(&tokens("x")),
+            Err(ParseError::TooManyArguments(0))
+        );
+        assert_eq!(
+            <(u32,)>::parse(&tokens("-1")),
+            Err(ParseError::InvalidArgument {
+                expected: "int",
+                got: "-1".to_owned()
+            })
+        );
+        assert_eq!(
+            <(String,)>::parse(&tokens(r#""a b""#)),
+            Ok(("a b".to_owned(),))
+        );
+    }
+
+    #[test]
+    fn usage_marks_optional_arguments() {
+        assert_eq!(
+            usage("fov", &<(f32, Option<bool>)>::specs()),
+            "fov <float> [bool]"
+        );
+        assert_eq!(usage("clear", &<()>::specs()), "clear");
+    }
+
+    const NAMES: [&str; 4] = ["clear", "fov", "fov_scale", "help"];
+
+    fn complete(line: &str) -> Completion {
+        let specs = <(f32, bool)>::specs();
+        Completion::new(line, NAMES, |name| {
+            (name == "fov").then_some(specs.as_slice())
+        })
+    }
+
+    #[test]
+    fn completes_commands() {
+        let completion = complete("fo");
+        assert_eq!(completion.start, 0);
+        assert_eq!(completion.candidates, ["fov", "fov_scale"]);
+        assert_eq!(completion.apply("fo").as_deref(), Some("fov"));
+
+        let completion = complete("he");
+        assert_eq!(completion.apply("he").as_deref(), Some("help "));
+
+        assert_eq!(complete("").candidates, NAMES);
+        assert!(complete("x").candidates.is_empty());
+        assert_eq!(complete("x").apply("x"), None);
+    }
+
+    #[test]
+    fn completes_arguments() {
+        // The first argument is a float, which has nothing to complete.
+        assert!(complete("fov ").candidates.is_empty());
+
+        let completion = complete("fov 90 t");
+        assert_eq!(completion.start, 7);
+        assert_eq!(completion.candidates, ["true"]);
+        assert_eq!(
+            completion.apply("fov 90 t").as_deref(),
+            Some("fov 90 true ")
+        );
+
+        assert_eq!(complete("fov 90 ").candidates, ["false", "true"]);
+        // Past the last argument, and of unknown commands.
+        assert!(complete("fov 90 true t").candidates.is_empty());
+        assert!(complete("help t").candidates.is_empty());
+    }
+
+    #[test]
+    fn completion_stops_at_what_candidates_share() {
+        let completion = Completion {
+            start: 0,
+            candidates: vec!["fov".to_owned(), "fov_scale".to_owned()],
+        };
+        // Nothing left to add.
+        assert_eq!(completion.apply("fov"), None);
+        assert_eq!(
+            common_prefix(&["abc".to_owned(), "abd".to_owned(), "ab".to_owned()]),
+            "ab"
+        );
+        assert_eq!(common_prefix(&["é1".to_owned(), "é2".to_owned()]), "é");
+        assert_eq!(common_prefix(&[]), "");
+    }
+}
@@ -0,0 +1,245 @@
+//! A dockable workspace for the overlay's panels, built on [`egui_dock`].
+//!
+//! Plugins register their panels as [`DockTab`]s with [`DockAppExt::add_dock_tab`]. The layout
+//! of the tabs is saved with the rest of the ui state by [`UiPersistence`].
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use egui_dock::{DockArea, DockState, NodeIndex, SurfaceIndex, TabViewer};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    render::Present,
+    ui::{
+        persistence::{self, UiPersistence},
+        PrimaryUiContext, UiContext, UiUpdate,
+    },
+};
+
+/// The [`UiPersistence`] panel key for the [`DockLayout`].
+const DOCK_LAYOUT_KEY: &str = "dock.layout";
+
+/// A panel that can be docked in the workspace.
+pub trait DockTab: Send + Sync + 'static {
+    /// Identifies the tab in the saved layout, so it should not change between versions.
+    const ID: &'static str;
+
+    fn title(&self) -> egui::WidgetText {
+        Self::ID.into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, world: &mut World);
+}
+
+/// Object safe part of [`DockTab`].
+trait ErasedDockTab: Send + Sync {
+    fn title(&self) -> egui::WidgetText;
+    fn ui(&mut self, ui: &mut egui::Ui, world: &mut World);
+}
+
+impl<T: DockTab> ErasedDockTab for T {
+    fn title(&self) -> egui::WidgetText {
+        DockTab::title(self)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, world: &mut World) {
+        DockTab::ui(self, ui, world)
+    }
+}
+
+/// The registered [`DockTab`]s, by id.
+#[derive(Default, Resource)]
+pub struct DockTabs {
+    tabs: BTreeMap<String, Box<dyn ErasedDockTab>>,
+}
+
+impl DockTabs {
+    pub fn contains(&self, id: &str) -> bool {
+        self.tabs.contains_key(id)
+    }
+
+    fn title(&self, id: &str) -> egui::WidgetText {
+        match self.tabs.get(id) {
+            Some(tab) => tab.title(),
+            None => id.into(),
+        }
+    }
+}
+
+pub trait DockAppExt {
+    /// Registers a tab, it is added to the workspace unless the user closed it before.
+    fn add_dock_tab<T: DockTab + FromWorld>(&mut self) -> &mut Self;
+}
+
+impl DockAppExt for App {
+    fn add_dock_tab<T: DockTab + FromWorld>(&mut self) -> &mut Self {
+        let tab = T::from_world(&mut self.world);
+        let mut tabs = self.world.get_resource_or_insert_with(DockTabs::default);
+        if tabs.tabs.insert(T::ID.to_owned(), Box::new(tab)).is_some() {
+            log::warn!("dock tab {} was registered twice", T::ID);
+        }
+        self
+    }
+}
+
+/// Where the tabs are docked, by id.
+#[derive(Resource, Serialize, Deserialize)]
+pub struct DockLayout {
+    state: DockState<String>,
+    /// Tabs the user closed, so that they are not added back on the next injection.
+    closed: BTreeSet<String>,
+}
+
+impl Default for DockLayout {
+    fn default() -> Self {
+        Self {
+            state: DockState::new(Vec::new()),
+            closed: BTreeSet::new(),
+        }
+    }
+}
+
+impl DockLayout {
+    /// Drops the tabs that are no longer registered and adds the ones that are new.
+    fn sync_with(&mut self, tabs: &DockTabs) {
+        let docked: Vec<String> = self
+            .state
+            .iter_nodes()
+            .filter_map(|node| node.tabs())
+            .flatten()
+            .cloned()
+            .collect();
+
+        for id in docked.iter().filter(|id| !tabs.contains(id)) {
+            if let Some(location) = self.state.find_tab(id) {
+                self.state.remove_tab(location);
+            }
+        }
+        self.closed.retain(|id| tabs.contains(id));
+        for id in tabs.tabs.keys() {
+            if !docked.contains(id) && !self.closed.contains(id) {
+                self.state.push_to_first_leaf(id.clone());
+            }
+        }
+    }
+}
+
+struct DockTabViewer<'a> {
+    world: &'a mut World,
+    tabs: &'a mut DockTabs,
+    closed: &'a mut BTreeSet<String>,
+    /// Tabs picked from the add popup, they are docked once the dock area is done with the state.
+    reopened: Vec<(SurfaceIndex, NodeIndex, String)>,
+}
+
+impl TabViewer for DockTabViewer<'_> {
+    type Tab = String;
+
+    fn title(&mut self, tab: &mut String) -> egui::WidgetText {
+        self.tabs.title(tab)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut String) {
+        if let Some(tab) = self.tabs.tabs.get_mut(tab.as_str()) {
+            tab.ui(ui, self.world);
+        }
+    }
+
+    fn id(&mut self, tab: &mut String) -> egui::Id {
+        egui::Id::new(tab.as_str())
+    }
+
+    fn on_close(&mut self, tab: &mut String) -> bool {
+        self.closed.insert(tab.clone());
+        true
+    }
+
+    fn add_popup(&mut self, ui: &mut egui::Ui, surface: SurfaceIndex, node: NodeIndex) {
+        ui.set_min_width(120.0);
+        if self.closed.is_empty() {
+            ui.label("All tabs are open");
+        }
+        for id in self.closed.iter() {
+            if ui.button(self.tabs.title(id)).clicked() {
+                self.reopened.push((surface, node, id.clone()));
+            }
+        }
+    }
+}
+
+/// Shows the workspace on the [`PrimaryUiContext`].
+pub fn dock_ui(
+    world: &mut World,
+    primary_ui_ctx: &mut QueryState<&UiContext, With<PrimaryUiContext>>,
+) {
+    let Ok(ctx) = primary_ui_ctx
+        .get_single(world)
+        .map(|ui_ctx| ui_ctx.ctx().clone())
+    else {
+        return;
+    };
+
+    world.resource_scope(|world, mut tabs: Mut<DockTabs>| {
+        world.resource_scope(|world, mut layout: Mut<DockLayout>| {
+            let DockLayout { state, closed } = layout.as_mut();
+            let mut viewer = DockTabViewer {
+                world,
+                tabs: tabs.as_mut(),
+                closed,
+                reopened: Vec::new(),
+            };
+
+            egui::Window::new("Workspace")
+                .default_size([640.0, 400.0])
+                .show(&ctx, |ui| {
+                    DockArea::new(state)
+                        .id(egui::Id::new("dock_area"))
+                        .style(egui_dock::Style::from_egui(ui.style()))
+                        .show_add_buttons(true)
+                        .show_add_popup(true)
+                        .show_inside(ui, &mut viewer);
+                });
+
+            for (surface, node, id) in viewer.reopened {
+                viewer.closed.remove(&id);
+                state.set_focused_node_and_surface((surface, node));
+                state.push_to_focused_leaf(id);
+            }
+        });
+    });
+}
+
+pub fn restore_dock_layout(
+    persistence: Res<UiPersistence>,
+    tabs: Res<DockTabs>,
+    mut layout: ResMut<DockLayout>,
+) {
+    if let Some(saved) = persistence.panel::<DockLayout>(DOCK_LAYOUT_KEY) {
+        *layout = saved;
+    }
+    layout.sync_with(&tabs);
+}
+
+/// Hands the layout to [`UiPersistence`] right before it is saved.
+pub fn store_dock_layout(layout: Res<DockLayout>, mut persistence: ResMut<UiPersistence>) {
+    persistence.set_panel(DOCK_LAYOUT_KEY, layout.as_ref());
+}
+
+pub struct DockPlugin;
+
+impl Plugin for DockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DockTabs>()
+            .init_resource::<DockLayout>()
+            .add_systems(Startup, restore_dock_layout)
+            .add_systems(Present, dock_ui.in_set(UiUpdate))
+            .add_systems(
+                Last,
+                store_dock_layout
+                    .before(persistence::save_ui_state)
+                    .run_if(persistence::ui_state_save_due),
+            );
+    }
+}
@@ -0,0 +1,234 @@
+//! Images for drawing in the ui and with the painters, e.g. team logos or weapon icons.
+//!
+//! Every egui context numbers its own textures, so images get a [`TextureId::User`] instead which
+//! is uploaded to every render target, making an [`OverlayImage`] usable with any context.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
+};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use egui::{load::SizedTexture, ColorImage, TextureId, TextureOptions};
+use epaint::{textures::TexturesDelta, ImageDelta};
+
+use crate::{
+    dock::{DockAppExt, DockTab},
+    render::RenderTargetHandle,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum OverlayImageError {
+    #[error("Failed to read image: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to decode image: {0}")]
+    Decode(#[from] image::ImageError),
+}
+
+#[derive(Debug)]
+struct ImageHandleInner {
+    id: TextureId,
+    size: [usize; 2],
+}
+
+/// A handle to an image in [`OverlayImages`], freed once the last clone of it is dropped.
+#[derive(Debug, Clone)]
+pub struct OverlayImage(Arc<ImageHandleInner>);
+
+impl OverlayImage {
+    /// The texture to paint with, the same for every ui and painter context, e.g. with
+    /// [`egui::Painter::image`] or [`epaint::Mesh::with_texture`].
+    pub fn id(&self) -> TextureId {
+        self.0.id
+    }
+
+    /// The size in pixels.
+    pub fn size(&self) -> [usize; 2] {
+        self.0.size
+    }
+
+    /// For [`egui::Image`], at its size in pixels. `egui::Image::new(&image)` does the same.
+    pub fn sized_texture(&self) -> SizedTexture {
+        SizedTexture::new(self.id(), [self.size()[0] as f32, self.size()[1] as f32])
+    }
+}
+
+impl From<&OverlayImage> for SizedTexture {
+    fn from(image: &OverlayImage) -> Self {
+        image.sized_texture()
+    }
+}
+
+struct StoredImage {
+    image: Arc<ColorImage>,
+    options: TextureOptions,
+    handle: Weak<ImageHandleInner>,
+}
+
+/// The images every render target should have, and which ones each of them has.
+#[derive(Default, Resource)]
+pub struct OverlayImages {
+    images: HashMap<TextureId, StoredImage>,
+    /// Images loaded from disk, so loading a file again shares the image while it is alive.
+    paths: HashMap<PathBuf, Weak<ImageHandleInner>>,
+    uploaded: HashMap<RenderTargetHandle, HashSet<TextureId>>,
+    next_id: u64,
+}
+
+impl OverlayImages {
+    pub fn insert(&mut self, image: ColorImage, options: TextureOptions) -> OverlayImage {
+        let id = TextureId::User(self.next_id);
+        self.next_id += 1;
+        let handle = OverlayImage(Arc::new(ImageHandleInner {
+            id,
+            size: image.size,
+        }));
+        self.images.insert(
+            id,
+            StoredImage {
+                image: Arc::new(image),
+                options,
+                handle: Arc::downgrade(&handle.0),
+            },
+        );
+        handle
+    }
+
+    /// Decodes a PNG or JPEG image.
+    pub fn load_bytes(&mut self, bytes: &[u8]) -> Result<OverlayImage, OverlayImageError> {
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+        let size = [image.width() as usize, image.height() as usize];
+        let image = ColorImage::from_rgba_unmultiplied(size, image.as_flat_samples().as_slice());
+        Ok(self.insert(image, TextureOptions::LINEAR))
+    }
+
+    /// Reads and decodes a PNG or JPEG file, or shares the image if the file is already loaded.
+    pub fn load(&mut self, path: &Path) -> Result<OverlayImage, OverlayImageError> {
+        if let Some(inner) = self.paths.get(path).and_then(Weak::upgrade) {
+            return Ok(OverlayImage(inner));
+        }
+        let image = self.load_bytes(&fs::read(path)?)?;
+        self.paths.insert(path.to_owned(), Arc::downgrade(&image.0));
+        Ok(image)
+    }
+
+    /// The images that are alive, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = OverlayImage> + '_ {
+        self.images
+            .values()
+            .filter_map(|stored| stored.handle.upgrade().map(OverlayImage))
+    }
+
+    /// Uploads everything to `render_target` again, e.g. after its renderer was set up again.
+    pub fn forget_uploads(&mut self, render_target: RenderTargetHandle) {
+        self.uploaded.remove(&render_target);
+    }
+
+    /// What `render_target` has to upload and free to be up to date, assuming it applies it.
+    pub fn textures_delta(&mut self, render_target: RenderTargetHandle) -> TexturesDelta {
+        self.images
+            .retain(|_, stored| stored.handle.strong_count() > 0);
+        self.paths.retain(|_, handle| handle.strong_count() > 0);
+
+        let uploaded = self.uploaded.entry(render_target).or_default();
+        let mut delta = TexturesDelta::default();
+        uploaded.retain(|id| {
+            let alive = self.images.contains_key(id);
+            if !alive {
+                delta.free.push(*id);
+            }
+            alive
+        });
+        for (id, stored) in &self.images {
+            if uploaded.insert(*id) {
+                delta
+                    .set
+                    .push((*id, ImageDelta::full(stored.image.clone(), stored.options)));
+            }
+        }
+        delta
+    }
+}
+
+/// Lists the live images, and loads files to try them out.
+#[derive(Default)]
+pub struct ImagesTab {
+    path: String,
+    /// Kept alive by the tab until removed.
+    loaded: Vec<OverlayImage>,
+    error: Option<String>,
+}
+
+impl DockTab for ImagesTab {
+    const ID: &'static str = "images";
+
+    fn title(&self) -> egui::WidgetText {
+        "Images".into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, world: &mut World) {
+        let mut images = world.resource_mut::<OverlayImages>();
+
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.path)
+                    .hint_text("path to a png or jpeg")
+                    .desired_width(240.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Load").clicked() || submitted {
+                match images.load(Path::new(self.path.trim())) {
+                    Ok(image) => {
+                        self.loaded.push(image);
+                        self.error = None;
+                    }
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.separator();
+
+        let mut removed = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for image in images.iter() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Image::new(&image).max_size(egui::vec2(64.0, 64.0)));
+                    ui.label(format!(
+                        "{:?} {}x{}",
+                        image.id(),
+                        image.size()[0],
+                        image.size()[1]
+                    ));
+                    let index = self
+                        .loaded
+                        .iter()
+                        .position(|loaded| loaded.id() == image.id());
+                    if let Some(index) = index {
+                        if ui.small_button("Remove").clicked() {
+                            removed = Some(index);
+                        }
+                    }
+                });
+            }
+        });
+        if let Some(index) = removed {
+            self.loaded.remove(index);
+        }
+    }
+}
+
+pub struct ImagesPlugin;
+
+impl Plugin for ImagesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OverlayImages>()
+            .add_dock_tab::<ImagesTab>();
+    }
+}
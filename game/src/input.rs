@@ -1,11 +1,17 @@
 use bevy_app::prelude::*;
 use bevy_ecs::{prelude::*, schedule::ScheduleLabel};
 use bevy_schedule_hook::prelude::*;
-use bevy_win32::{window::Win32Windows, WinMessageEvent};
+use bevy_utils::HashMap;
+use bevy_win32::{window::Win32Windows, WinMessageEvent, WindowThreadState};
+use bevy_window::Window;
 use binsig::Pattern;
-use windows::Win32::UI::WindowsAndMessaging::MSG;
+use windows::Win32::UI::WindowsAndMessaging::{MSG, WM_NULL};
 
-use crate::utils::{get_module, module_addr, module_to_bytes};
+use crate::{
+    overlay::{InputMessageKind, OverlayState},
+    ui::UiContext,
+    utils::{get_module, module_addr, module_to_bytes},
+};
 
 /// The schedule that assumes the role of `CallWndProcFn`.
 ///
@@ -26,19 +32,52 @@ fn hook_overlay_input(mut detours: ResMut<Detours>) {
     detours.enable_detour(OverlayMessageHandler);
 }
 
+/// Forwards the message to bevy, and keeps it from the game when the overlay takes the input.
+///
+/// Also applies the cursor and IME position of the window, which can only be done from its thread.
 pub fn message_handler(
     windows: Res<Win32Windows>,
     input: NonSend<OverlayMessageHandlerInput>,
+    overlay_state: Res<OverlayState>,
+    ui_contexts: Query<&UiContext>,
+    bevy_windows: Query<&Window>,
+    mut thread_states: Local<HashMap<Entity, WindowThreadState>>,
     mut wm_event: EventWriter<bevy_win32::WinMessageEvent>,
 ) {
-    // TODO: We need a way to stop game from receiving events...
     let msg = unsafe { input.__arg_0.read() };
-    wm_event.send(WinMessageEvent {
-        msg: msg.message,
-        wparam: msg.wParam,
-        lparam: msg.lParam,
-        window: windows.get_window_entity(msg.hwnd.into()).unwrap(),
-    });
+    // Windows bevy does not know of yet are left to the game.
+    let Some(window) = windows.get_window_entity(msg.hwnd.into()) else {
+        return;
+    };
+    // SAFETY: The message is being handled until this hook returns.
+    wm_event.send(unsafe { WinMessageEvent::from_msg(window, &msg) });
+
+    let (wants_pointer_input, wants_keyboard_input) = match ui_contexts.get(window) {
+        Ok(ui_ctx) => (
+            ui_ctx.ctx().wants_pointer_input(),
+            ui_ctx.ctx().wants_keyboard_input(),
+        ),
+        Err(_) => (false, false),
+    };
+
+    if let Ok(bevy_window) = bevy_windows.get(window) {
+        // The game keeps its own cursor unless the overlay has the pointer.
+        let set_cursor = *overlay_state == OverlayState::Interactive
+            || (overlay_state.is_visible() && wants_pointer_input);
+        let state = thread_states.entry(window).or_default();
+        if bevy_win32::apply_on_window_thread(&msg, bevy_window, state, set_cursor) {
+            unsafe { (*input.__arg_0).message = WM_NULL };
+            return;
+        }
+    }
+
+    let Some(kind) = InputMessageKind::from_message(msg.message) else {
+        return;
+    };
+    if overlay_state.should_consume(kind, wants_pointer_input, wants_keyboard_input) {
+        // The game skips `WM_NULL`, so this is how we swallow the message.
+        unsafe { (*input.__arg_0).message = WM_NULL };
+    }
 }
 
 // TODO: We cant otherwise it will go and call itself...
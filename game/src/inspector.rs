@@ -0,0 +1,247 @@
+//! A dock tab for looking into the world, editing what is reflected in place.
+//!
+//! Components and resources are editable when their type is registered with
+//! [`AppTypeRegistry`] along with `#[reflect(Component)]` or `#[reflect(Resource)]`, everything
+//! else is only listed by name.
+
+use std::any::TypeId;
+
+use bevy_app::prelude::*;
+use bevy_ecs::{component::ComponentId, prelude::*, reflect::AppTypeRegistry};
+use bevy_reflect::TypeRegistry;
+use bevy_utils::get_short_name;
+
+use crate::dock::{DockAppExt, DockTab};
+
+use self::field::reflect_ui;
+
+pub mod field;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Selection {
+    Entity(Entity),
+    Resource(ComponentId),
+}
+
+#[derive(Default)]
+pub struct InspectorTab {
+    selected: Option<Selection>,
+    /// Shown above the selection until unpinned.
+    pinned: Vec<Entity>,
+    /// Only lists entities with a component whose name contains this.
+    component_filter: String,
+}
+
+/// The short names of the components of `entity`, and the type ids of the rust types.
+fn entity_components(world: &World, entity: Entity) -> Vec<(String, Option<TypeId>)> {
+    world
+        .inspect_entity(entity)
+        .into_iter()
+        .map(|info| (get_short_name(info.name()), info.type_id()))
+        .collect()
+}
+
+fn entity_label(entity: Entity, components: &[(String, Option<TypeId>)]) -> String {
+    let mut names: Vec<&str> = components
+        .iter()
+        .take(3)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if components.len() > names.len() {
+        names.push("...");
+    }
+    format!("{:?} ({})", entity, names.join(", "))
+}
+
+fn entity_ui(ui: &mut egui::Ui, world: &mut World, registry: &TypeRegistry, entity: Entity) {
+    let id = egui::Id::new(("inspector_entity", entity));
+    for (name, type_id) in entity_components(world, entity) {
+        let reflect_component = type_id
+            .and_then(|type_id| registry.get_type_data::<ReflectComponent>(type_id))
+            .cloned();
+        let Some(reflect_component) = reflect_component else {
+            ui.label(&name).on_hover_text("Not reflected");
+            continue;
+        };
+
+        egui::CollapsingHeader::new(&name)
+            .id_source(id.with(&name))
+            .show(ui, |ui| {
+                let mut entity_mut = world.entity_mut(entity);
+                let Some(mut value) = reflect_component.reflect_mut(&mut entity_mut) else {
+                    return;
+                };
+                // Only mark the component as changed if it was edited.
+                if reflect_ui(ui, value.bypass_change_detection(), id.with(&name)) {
+                    value.set_changed();
+                }
+            });
+    }
+}
+
+/// The resources in the world, with the reflection data of those that have it.
+fn resources(
+    world: &World,
+    registry: &TypeRegistry,
+) -> Vec<(ComponentId, String, Option<ReflectResource>)> {
+    let mut resources: Vec<_> = world
+        .components()
+        .iter()
+        .filter(|info| world.get_resource_by_id(info.id()).is_some())
+        .map(|info| {
+            let reflect_resource = info
+                .type_id()
+                .and_then(|type_id| registry.get_type_data::<ReflectResource>(type_id))
+                .cloned();
+            (info.id(), get_short_name(info.name()), reflect_resource)
+        })
+        .collect();
+    resources.sort_by(|(_, a, _), (_, b, _)| a.cmp(b));
+    resources
+}
+
+fn resource_ui(
+    ui: &mut egui::Ui,
+    world: &mut World,
+    registry: &TypeRegistry,
+    component_id: ComponentId,
+) {
+    let Some((_, name, reflect_resource)) = resources(world, registry)
+        .into_iter()
+        .find(|(id, ..)| *id == component_id)
+    else {
+        return;
+    };
+    ui.heading(&name);
+    let Some(reflect_resource) = reflect_resource else {
+        ui.weak("Not reflected");
+        return;
+    };
+    let Some(mut value) = reflect_resource.reflect_mut(world) else {
+        return;
+    };
+    let id = egui::Id::new(("inspector_resource", component_id));
+    if reflect_ui(ui, value.bypass_change_detection(), id) {
+        value.set_changed();
+    }
+}
+
+impl InspectorTab {
+    fn list_ui(&mut self, ui: &mut egui::Ui, world: &World, registry: &TypeRegistry) {
+        ui.add(
+            egui::TextEdit::singleline(&mut self.component_filter)
+                .hint_text("component filter")
+                .desired_width(f32::INFINITY),
+        );
+        let filter = self.component_filter.to_lowercase();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::CollapsingHeader::new("Entities")
+                .default_open(true)
+                .show(ui, |ui| {
+                    for entity in world.iter_entities().map(|entity| entity.id()) {
+                        let components = entity_components(world, entity);
+                        if !filter.is_empty()
+                            && !components
+                                .iter()
+                                .any(|(name, _)| name.to_lowercase().contains(&filter))
+                        {
+                            continue;
+                        }
+                        let selection = Selection::Entity(entity);
+                        let label = entity_label(entity, &components);
+                        if ui
+                            .selectable_label(self.selected == Some(selection), label)
+                            .clicked()
+                        {
+                            self.selected = Some(selection);
+                        }
+                    }
+                });
+
+            egui::CollapsingHeader::new("Resources").show(ui, |ui| {
+                for (id, name, reflect_resource) in resources(world, registry) {
+                    let selection = Selection::Resource(id);
+                    let text = match reflect_resource {
+                        Some(_) => egui::RichText::new(name),
+                        None => egui::RichText::new(name).weak(),
+                    };
+                    if ui
+                        .selectable_label(self.selected == Some(selection), text)
+                        .clicked()
+                    {
+                        self.selected = Some(selection);
+                    }
+                }
+            });
+        });
+    }
+
+    fn details_ui(&mut self, ui: &mut egui::Ui, world: &mut World, registry: &TypeRegistry) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let mut unpinned = None;
+            for &entity in &self.pinned {
+                ui.horizontal(|ui| {
+                    ui.strong(format!("{:?}", entity));
+                    if ui.small_button("Unpin").clicked() {
+                        unpinned = Some(entity);
+                    }
+                });
+                entity_ui(ui, world, registry, entity);
+                ui.separator();
+            }
+            self.pinned.retain(|entity| Some(*entity) != unpinned);
+
+            match self.selected {
+                Some(Selection::Entity(entity)) if !self.pinned.contains(&entity) => {
+                    ui.horizontal(|ui| {
+                        ui.strong(format!("{:?}", entity));
+                        if ui.small_button("Pin").clicked() {
+                            self.pinned.push(entity);
+                        }
+                    });
+                    entity_ui(ui, world, registry, entity);
+                }
+                Some(Selection::Resource(component_id)) => {
+                    resource_ui(ui, world, registry, component_id);
+                }
+                _ => {}
+            }
+        });
+    }
+}
+
+impl DockTab for InspectorTab {
+    const ID: &'static str = "inspector";
+
+    fn title(&self) -> egui::WidgetText {
+        "Inspector".into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, world: &mut World) {
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+
+        self.pinned
+            .retain(|entity| world.get_entity(*entity).is_some());
+        if let Some(Selection::Entity(entity)) = self.selected {
+            if world.get_entity(entity).is_none() {
+                self.selected = None;
+            }
+        }
+
+        egui::SidePanel::left("inspector_list")
+            .resizable(true)
+            .default_width(220.0)
+            .show_inside(ui, |ui| self.list_ui(ui, world, &registry));
+        egui::CentralPanel::default().show_inside(ui, |ui| self.details_ui(ui, world, &registry));
+    }
+}
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_dock_tab::<InspectorTab>();
+    }
+}
@@ -0,0 +1,140 @@
+//! Editors for reflected values.
+
+use bevy_reflect::{
+    DynamicEnum, DynamicVariant, Reflect, ReflectMut, ReflectRef, TypeInfo, VariantInfo,
+};
+
+/// Shows an editor for `value`, returning whether it was changed.
+pub fn reflect_ui(ui: &mut egui::Ui, value: &mut dyn Reflect, id: egui::Id) -> bool {
+    let mut changed = false;
+    match value.reflect_mut() {
+        ReflectMut::Struct(value) => {
+            for index in 0..value.field_len() {
+                let name = value.name_at(index).unwrap_or_default().to_owned();
+                if let Some(field) = value.field_at_mut(index) {
+                    changed |= field_ui(ui, &name, field, id.with(index));
+                }
+            }
+        }
+        ReflectMut::TupleStruct(value) => {
+            for index in 0..value.field_len() {
+                if let Some(field) = value.field_mut(index) {
+                    changed |= field_ui(ui, &index.to_string(), field, id.with(index));
+                }
+            }
+        }
+        ReflectMut::Tuple(value) => {
+            for index in 0..value.field_len() {
+                if let Some(field) = value.field_mut(index) {
+                    changed |= field_ui(ui, &index.to_string(), field, id.with(index));
+                }
+            }
+        }
+        ReflectMut::List(value) => {
+            for index in 0..value.len() {
+                if let Some(item) = value.get_mut(index) {
+                    changed |= field_ui(ui, &format!("[{}]", index), item, id.with(index));
+                }
+            }
+        }
+        ReflectMut::Array(value) => {
+            for index in 0..value.len() {
+                if let Some(item) = value.get_mut(index) {
+                    changed |= field_ui(ui, &format!("[{}]", index), item, id.with(index));
+                }
+            }
+        }
+        ReflectMut::Map(value) => {
+            for index in 0..value.len() {
+                if let Some((key, item)) = value.get_at_mut(index) {
+                    let key = format!("{:?}", key);
+                    changed |= field_ui(ui, &key, item, id.with(index));
+                }
+            }
+        }
+        ReflectMut::Enum(value) => {
+            let variant = value.variant_name().to_owned();
+            let unit_variants: Vec<&'static str> = match value.get_represented_type_info() {
+                Some(TypeInfo::Enum(info)) => info
+                    .iter()
+                    .filter_map(|variant| match variant {
+                        VariantInfo::Unit(unit) => Some(unit.name()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            // Only switching to variants without fields, since we would have to make up their values.
+            let mut picked = None;
+            if unit_variants.is_empty() {
+                ui.label(&variant);
+            } else {
+                egui::ComboBox::from_id_source(id.with("variant"))
+                    .selected_text(&variant)
+                    .show_ui(ui, |ui| {
+                        for name in unit_variants {
+                            if ui.selectable_label(name == variant, name).clicked() {
+                                picked = Some(name);
+                            }
+                        }
+                    });
+            }
+            if let Some(name) = picked.filter(|name| *name != variant) {
+                value.apply(&DynamicEnum::new(name, DynamicVariant::Unit));
+                return true;
+            }
+
+            for index in 0..value.field_len() {
+                let name = value
+                    .name_at(index)
+                    .map_or_else(|| index.to_string(), str::to_owned);
+                if let Some(field) = value.field_at_mut(index) {
+                    changed |= field_ui(ui, &name, field, id.with(index));
+                }
+            }
+        }
+        ReflectMut::Value(value) => changed = value_ui(ui, value),
+    }
+    changed
+}
+
+/// Shows a named field, collapsible if it has fields of its own.
+fn field_ui(ui: &mut egui::Ui, name: &str, field: &mut dyn Reflect, id: egui::Id) -> bool {
+    if matches!(field.reflect_ref(), ReflectRef::Value(_)) {
+        return ui
+            .horizontal(|ui| {
+                ui.label(name);
+                value_ui(ui, field)
+            })
+            .inner;
+    }
+    egui::CollapsingHeader::new(name)
+        .id_source(id)
+        .show(ui, |ui| reflect_ui(ui, field, id))
+        .body_returned
+        .unwrap_or(false)
+}
+
+/// Edits the primitive values we know of, others are only shown.
+fn value_ui(ui: &mut egui::Ui, value: &mut dyn Reflect) -> bool {
+    macro_rules! drag_value {
+        ($($ty:ty),*) => {
+            $(
+                if let Some(value) = value.downcast_mut::<$ty>() {
+                    return ui.add(egui::DragValue::new(value)).changed();
+                }
+            )*
+        };
+    }
+    drag_value!(f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+    if let Some(value) = value.downcast_mut::<bool>() {
+        return ui.checkbox(value, "").changed();
+    }
+    if let Some(value) = value.downcast_mut::<String>() {
+        return ui.text_edit_singleline(value).changed();
+    }
+    ui.weak(format!("{:?}", value));
+    false
+}
@@ -2,18 +2,21 @@
 
 use std::ffi::c_void;
 
-use bevy_app::{App, PostStartup};
+use bevy_app::{App, AppExit, PostStartup};
 use bevy_ecs::{
-    event::EventWriter,
+    event::{EventReader, EventWriter},
+    query::With,
     schedule::IntoSystemConfigs,
-    system::{Query, ResMut},
+    system::{Commands, Query, Res},
     world::{FromWorld, World},
 };
+use bevy_math::{Vec2, Vec3};
 use bevy_schedule_hook::{prelude::DispatchPlugin, DetourPlugin};
 use bevy_utils::Duration;
 use bevy_win32::Win32Plugin;
+use dock::{DockAppExt, DockPlugin, DockTab};
 use egui::Align2;
-use epaint::Color32;
+use epaint::{Color32, Stroke};
 use ui::UiPlugin;
 use windows::Win32::{
     Foundation::{CloseHandle, BOOL, HINSTANCE},
@@ -26,21 +29,37 @@ use windows::Win32::{
 };
 
 use crate::{
+    console::ConsolePlugin,
+    images::ImagesPlugin,
     input::OverlayInputPlugin,
+    inspector::InspectorPlugin,
     logger::LogPlugin,
-    paint::{PaintPlugin, PainterContext, PainterUpdate},
+    overlay::OverlayPlugin,
+    paint::{
+        projection::{Painter3d, ViewProjection},
+        shapes::{PaintOpacity, PaintRect, PaintText, PaintTransform},
+        tween::{self, Ease, Tween, TweenCompleted, TweenDone, TweenValue},
+        PaintPlugin, PainterContext, PainterUpdate, PrimaryPainterContext,
+    },
     profiler::ProfilerPlugin,
     render::{Present, RenderPlugin},
-    ui::{UiContext, UiUpdate},
+    schedule_viewer::ScheduleViewerPlugin,
+    ui::{PrimaryUi, UiUpdate},
     utils::get_window_hwnd,
 };
 
+mod console;
 mod cs2;
+mod dock;
+mod images;
 mod input;
+mod inspector;
 mod logger;
+mod overlay;
 mod paint;
 mod profiler;
 mod render;
+mod schedule_viewer;
 mod ui;
 mod utils;
 
@@ -97,54 +116,119 @@ unsafe extern "system" fn attach_thread(_lp_module: *mut c_void) -> u32 {
                 arc_app.lock().unwrap().update();
             }
         }
-    }
 
-    fn paint_test(mut query: Query<&mut PainterContext>) {
-        let mut p = query.get_single_mut().unwrap();
-        let mut p = p.get_mut();
-
-        p.rect_filled(
-            epaint::Rect::from_center_size(
-                epaint::Pos2 {
-                    x: 1000.0,
-                    y: 1000.0,
-                },
-                epaint::vec2(250.0, 250.0),
-            ),
-            5.0,
-            Color32::GREEN,
-        );
+        // Give everything a last update to react to the exit, e.g. saving state.
+        let mut locked_app = arc_app.lock().unwrap();
+        locked_app.world.send_event(AppExit);
+        locked_app.update();
+    }
 
-        p.text(
-            epaint::Pos2 {
-                x: 1000.0,
-                y: 900.0,
+    fn spawn_paint_test(mut commands: Commands) {
+        commands.spawn((
+            PaintRect {
+                size: Vec2::splat(250.0),
+                rounding: 5.0,
+                fill: Color32::GREEN,
+                ..Default::default()
+            },
+            PaintTransform::from_xy(1000.0, 1000.0),
+            pulse_tween(),
+        ));
+        commands.spawn((
+            PaintText {
+                text: "HELLO FROM PAINT".to_owned(),
+                font: epaint::FontId::proportional(20.0),
+                color: Color32::RED,
+                anchor: Align2::LEFT_TOP,
             },
-            Align2::LEFT_TOP,
-            "HELLO FROM PAINT",
-            epaint::FontId::proportional(20.0),
-            Color32::RED,
+            PaintTransform::from_xy(1000.0, 900.0),
+            PaintOpacity(0.0),
+            Tween::to(
+                TweenValue::Alpha(1.0),
+                Duration::from_millis(500),
+                Ease::CubicOut,
+            )
+            .with(TweenValue::Translation(Vec2::new(1000.0, 880.0))),
+        ));
+    }
+
+    fn pulse_paint_test(
+        mut commands: Commands,
+        mut ev_completed: EventReader<TweenCompleted>,
+        rects: Query<(), With<PaintRect>>,
+    ) {
+        for ev in ev_completed.iter() {
+            if rects.contains(ev.entity) {
+                commands.entity(ev.entity).insert(pulse_tween());
+            }
+        }
+    }
+
+    fn pulse_tween() -> Tween {
+        Tween::to(
+            TweenValue::Scale(1.1),
+            Duration::from_millis(300),
+            Ease::BackOut,
+        )
+        .then(
+            TweenValue::Scale(1.0),
+            Duration::from_millis(300),
+            Ease::SineInOut,
+        )
+        .delay(Duration::from_secs(1))
+        .on_done(TweenDone::Remove)
+    }
+
+    fn paint_3d_test(
+        mut painter: Query<&mut PainterContext, With<PrimaryPainterContext>>,
+        view: Res<ViewProjection>,
+    ) {
+        let Ok(mut painter) = painter.get_single_mut() else {
+            return;
+        };
+        let painter = painter.get_mut();
+        let stroke = Stroke {
+            width: 1.0,
+            color: Color32::YELLOW,
+        };
+
+        painter.box_3d(&view, Vec3::splat(-0.1), Vec3::splat(0.1), stroke);
+        painter.sphere_3d(&view, Vec3::ZERO, 0.1, stroke);
+        painter.line_3d(&view, Vec3::ZERO, Vec3::X * 0.2, stroke);
+        painter.text_3d(
+            &view,
+            Vec3::ZERO,
+            Align2::CENTER_BOTTOM,
+            "ORIGIN",
+            epaint::FontId::proportional(14.0),
+            Color32::YELLOW,
         );
     }
 
     fn create_painter(world: &mut World) {
         let t = PainterContext::from_world(world);
-        world.spawn(t);
+        world.spawn((t, PrimaryPainterContext));
     }
 
-    fn test_ui(mut ui_ctx: ResMut<UiContext>) {
-        ui_ctx.get_mut().debug_painter().circle_filled(
-            (100.0, 100.0).into(),
-            50.0,
-            egui::Color32::LIGHT_RED,
-        );
+    fn test_ui(mut primary_ui: PrimaryUi) {
+        let Some(ctx) = primary_ui.ctx_mut() else {
+            return;
+        };
+
+        ctx.debug_painter()
+            .circle_filled((100.0, 100.0).into(), 50.0, egui::Color32::LIGHT_RED);
+    }
+
+    #[derive(Default)]
+    struct DrainTab;
 
-        egui::Window::new("drain")
-            .fixed_pos((100.0 as f32, 200.0))
-            .show(ui_ctx.get_mut(), |ui| {
-                ui.label("Hello world!");
-                // ui_ctx.get_mut().settings_ui(ui);
-            });
+    impl DockTab for DrainTab {
+        const ID: &'static str = "drain";
+
+        fn ui(&mut self, ui: &mut egui::Ui, _world: &mut World) {
+            ui.label("Hello world!");
+            // ui_ctx.get_mut().settings_ui(ui);
+        }
     }
 
     fn add_primary_window(mut aw_event: EventWriter<bevy_win32::AddWindowEvent>) {
@@ -166,16 +250,33 @@ unsafe extern "system" fn attach_thread(_lp_module: *mut c_void) -> u32 {
             },
             Win32Plugin,
             OverlayInputPlugin,
+            OverlayPlugin,
             ProfilerPlugin,
             DetourPlugin,
             RenderPlugin,
             UiPlugin,
             PaintPlugin,
+            ImagesPlugin,
+        ))
+        .add_plugins((
+            DockPlugin,
+            ConsolePlugin,
+            InspectorPlugin,
+            ScheduleViewerPlugin,
         ))
-        .add_systems(PostStartup, (create_painter, add_primary_window))
+        .add_dock_tab::<DrainTab>()
+        .add_systems(
+            PostStartup,
+            (create_painter, add_primary_window, spawn_paint_test),
+        )
         .add_systems(
             Present,
-            (paint_test.in_set(PainterUpdate), test_ui.in_set(UiUpdate)),
+            (
+                paint_3d_test.in_set(PainterUpdate),
+                // The tween removes itself once done, which has to come before the next one.
+                pulse_paint_test.after(tween::tween_shapes),
+                test_ui.in_set(UiUpdate),
+            ),
         );
 
     // TODO: THIS NEEDS TO BE REDONE, WE NEED TO MAKE OUR OWN RUNNER THAT MIMICKS THE REGULAR ONE... OR REUSE REGULAR ONE...
@@ -1,13 +1,170 @@
-use bevy_app::{App, Plugin};
-use log::Level;
+use std::{
+    collections::VecDeque,
+    panic::PanicHookInfo,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use bevy_app::{App, AppExit, Last, Plugin};
+use bevy_ecs::prelude::*;
+use log::{Level, LevelFilter};
+use simple_logger::SimpleLogger;
+
+use crate::{dock::DockAppExt, ui::persistence, utils};
+
+use self::{file::RotatingLogFile, filter::LogFilter, viewer::LogViewerTab};
+
+pub mod file;
+pub mod filter;
+pub mod viewer;
+
+/// How many records [`LogRecords`] keeps by default.
+const LOG_RECORDS_CAPACITY: usize = 2048;
+
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    /// Counts up with every record, for telling which records were already seen.
+    pub index: u64,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+struct LogRecordsInner {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+    next_index: u64,
+}
+
+/// A ring buffer of the latest log records, shared with the logger.
+#[derive(Clone, Resource)]
+pub struct LogRecords(Arc<Mutex<LogRecordsInner>>);
+
+impl LogRecords {
+    pub fn new(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(LogRecordsInner {
+            records: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            next_index: 0,
+        })))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.lock().unwrap().capacity
+    }
+
+    fn push(&self, record: &log::Record) {
+        let mut inner = self.0.lock().unwrap();
+        let index = inner.next_index;
+        inner.next_index += 1;
+        if inner.records.len() == inner.capacity {
+            inner.records.pop_front();
+        }
+        inner.records.push_back(LogRecord {
+            index,
+            level: record.level(),
+            target: record.target().to_owned(),
+            message: record.args().to_string(),
+        });
+    }
+
+    /// Calls `f` with every record from `*next_index` on, and moves `next_index` past them.
+    pub fn read_since(&self, next_index: &mut u64, mut f: impl FnMut(&LogRecord)) {
+        let inner = self.0.lock().unwrap();
+        for record in inner.records.iter().filter(|r| r.index >= *next_index) {
+            f(record);
+        }
+        *next_index = inner.next_index;
+    }
+}
+
+/// Filters the records, then logs them to the console through [`SimpleLogger`], to the log files
+/// and keeps them in [`LogRecords`].
+struct CaptureLogger {
+    inner: SimpleLogger,
+    filter: LogFilter,
+    level: Level,
+    records: LogRecords,
+    file: Option<Arc<Mutex<RotatingLogFile>>>,
+}
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level && self.filter.enabled(metadata.target(), metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        self.inner.log(record);
+        self.records.push(record);
+        if let Some(file) = &self.file {
+            let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Err(err) = file.write_line(&file::format_record(record)) {
+                eprintln!("failed to write log file: {}", err);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap_or_else(PoisonError::into_inner).flush();
+        }
+    }
+}
+
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Send + Sync>;
+
+/// The panic hook that was set before ours, put back by [`detach_logger`].
+#[derive(Resource)]
+pub struct PreviousPanicHook(Option<Arc<PanicHook>>);
+
+/// Writes the panic to the log file before the previous hook runs, so crash reports have it.
+fn flush_log_file_on_panic(file: Arc<Mutex<RotatingLogFile>>) -> PreviousPanicHook {
+    let previous_hook = Arc::new(std::panic::take_hook());
+    let hook = previous_hook.clone();
+    std::panic::set_hook(Box::new(move |info| {
+        // Skipped while the file is locked, by another thread writing to it or by this thread
+        // having panicked while writing to it.
+        if let Ok(mut file) = file.try_lock() {
+            let line = file::format_record(
+                &log::Record::builder()
+                    .level(Level::Error)
+                    .target("panic")
+                    .args(format_args!("{}", info))
+                    .build(),
+            );
+            let _ = file.write_line(&line);
+            let _ = file.flush();
+        }
+        hook(info);
+    }));
+    PreviousPanicHook(Some(previous_hook))
+}
 
 pub struct LogPlugin {
-    /// Filters logs using the [`EnvFilter`] format
+    /// Filters logs with [`LogFilter`] directives, e.g. `info,cs2::render=debug,egui=warn`.
+    ///
+    /// Everything up to `level` is logged when empty.
     pub filter: String,
 
     /// Filters out logs that are "less than" the given level.
     /// This can be further filtered using the `filter` setting.
     pub level: Level,
+
+    /// How many records [`LogRecords`] keeps for the in-overlay views of the log.
+    pub capacity: usize,
+
+    /// Where the log files are written, `None` to only log to the console.
+    pub dir: Option<PathBuf>,
+
+    /// The size in bytes after which the log file is rotated.
+    pub max_file_size: u64,
+
+    /// How many log files are kept, including the current one.
+    pub max_files: usize,
 }
 
 impl Default for LogPlugin {
@@ -15,14 +172,77 @@ impl Default for LogPlugin {
         Self {
             filter: "".to_string(),
             level: Level::Trace,
+            capacity: LOG_RECORDS_CAPACITY,
+            dir: utils::config_dir().map(|dir| dir.join("logs")),
+            max_file_size: 4 * 1024 * 1024,
+            max_files: 5,
         }
     }
 }
 
-impl Plugin for LogPlugin {
-    fn build(&self, _app: &mut App) {
-        simple_logger::init_with_level(self.level).unwrap();
+/// Flushes the log and stops logging when we are about to be uninjected.
+pub fn detach_logger(
+    mut ev_exit: EventReader<AppExit>,
+    previous_hook: Option<ResMut<PreviousPanicHook>>,
+) {
+    if ev_exit.iter().count() == 0 {
+        return;
+    }
+    log::info!("detaching logger");
+    log::logger().flush();
+    log::set_max_level(LevelFilter::Off);
+    // Our panic hook must not outlive the module, so the one it wraps is put back.
+    let Some(previous_hook) = previous_hook.and_then(|mut hook| hook.0.take()) else {
+        return;
+    };
+    drop(std::panic::take_hook());
+    // Dropping our hook dropped its reference, so this is the last one.
+    if let Ok(previous_hook) = Arc::try_unwrap(previous_hook) {
+        std::panic::set_hook(previous_hook);
     }
+}
 
-    // TODO: On destroy we need to do stuff.
+impl Plugin for LogPlugin {
+    fn build(&self, app: &mut App) {
+        let (filter, filter_err) = match self.filter.trim() {
+            "" => (LogFilter::new(LevelFilter::Trace), None),
+            filter => match filter.parse() {
+                Ok(filter) => (filter, None),
+                Err(err) => (LogFilter::new(LevelFilter::Trace), Some(err)),
+            },
+        };
+        let max_level = filter.max_level().min(self.level.to_level_filter());
+        let records = LogRecords::new(self.capacity);
+        let file = self.dir.clone().map(|dir| {
+            Arc::new(Mutex::new(RotatingLogFile::new(
+                dir,
+                self.max_file_size,
+                self.max_files,
+            )))
+        });
+        if let Some(file) = &file {
+            app.insert_resource(flush_log_file_on_panic(file.clone()));
+        }
+
+        simple_logger::set_up_color_terminal();
+        log::set_boxed_logger(Box::new(CaptureLogger {
+            // We do the filtering, so let everything through.
+            inner: SimpleLogger::new()
+                .with_level(LevelFilter::Trace)
+                .with_threads(true),
+            filter,
+            level: self.level,
+            records: records.clone(),
+            file,
+        }))
+        .unwrap();
+        log::set_max_level(max_level);
+        if let Some(err) = filter_err {
+            log::warn!("ignoring log filter {:?}: {}", self.filter, err);
+        }
+
+        app.insert_resource(records)
+            .add_dock_tab::<LogViewerTab>()
+            .add_systems(Last, detach_logger.after(persistence::save_ui_state));
+    }
 }
@@ -0,0 +1,113 @@
+//! Writes the log to size rotated files, so it outlives the console when the game crashes.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, LineWriter, Write},
+    path::PathBuf,
+};
+
+use time::{format_description::FormatItem, OffsetDateTime};
+
+/// The same as the console's timestamps.
+const TIMESTAMP_FORMAT: &[FormatItem] = time::macros::format_description!(
+    "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z"
+);
+
+const LOG_FILE_NAME: &str = "overlay";
+
+/// Formats a record the way [`simple_logger`] prints it to the console, without the colours.
+pub fn format_record(record: &log::Record) -> String {
+    let target = match record.target() {
+        "" => record.module_path().unwrap_or_default(),
+        target => target,
+    };
+    let thread = std::thread::current();
+    format!(
+        "{} {:<5} [{}@{}] {}",
+        OffsetDateTime::now_utc()
+            .format(TIMESTAMP_FORMAT)
+            .unwrap_or_default(),
+        record.level(),
+        target,
+        thread.name().unwrap_or("?"),
+        record.args()
+    )
+}
+
+/// `overlay.log` is the newest file, `overlay.1.log` the one before it and so on.
+pub struct RotatingLogFile {
+    dir: PathBuf,
+    /// Rotates once the newest file would grow past this many bytes.
+    max_size: u64,
+    /// How many files are kept, including the newest one.
+    max_files: usize,
+    /// Every line is flushed right away, so that nothing is lost when the game crashes.
+    file: Option<LineWriter<File>>,
+    size: u64,
+}
+
+impl RotatingLogFile {
+    pub fn new(dir: PathBuf, max_size: u64, max_files: usize) -> Self {
+        Self {
+            dir,
+            max_size,
+            max_files: max_files.max(1),
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        match index {
+            0 => self.dir.join(format!("{}.log", LOG_FILE_NAME)),
+            index => self.dir.join(format!("{}.{}.log", LOG_FILE_NAME, index)),
+        }
+    }
+
+    fn open(&mut self) -> io::Result<&mut LineWriter<File>> {
+        if self.file.is_none() {
+            fs::create_dir_all(&self.dir)?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.path(0))?;
+            self.size = file.metadata()?.len();
+            self.file = Some(LineWriter::new(file));
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    /// Shifts every file up by one, dropping the oldest.
+    fn rotate(&mut self) -> io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        let _ = fs::remove_file(self.path(self.max_files - 1));
+        for index in (0..self.max_files - 1).rev() {
+            let from = self.path(index);
+            if from.exists() {
+                fs::rename(from, self.path(index + 1))?;
+            }
+        }
+        self.size = 0;
+        Ok(())
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        self.open()?;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.open()?, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Per-module log filtering with directives like `info,cs2::render=debug,egui=warn`.
+
+use std::str::FromStr;
+
+use log::LevelFilter;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LogFilterError {
+    #[error("Invalid log level {0:?}")]
+    InvalidLevel(String),
+
+    #[error("Empty target in directive {0:?}")]
+    EmptyTarget(String),
+}
+
+/// A comma separated list of directives, each either a level that applies to every target, a
+/// `target=level` pair, or a lone target which enables everything from it.
+///
+/// The directive of the longest matching target wins, where `a::b` matches the targets `a::b`
+/// and `a::b::c` but not `a::bc`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    /// Sorted by target length, longest first.
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    /// Lets everything up to `level` through.
+    pub fn new(level: LevelFilter) -> Self {
+        Self {
+            default: level,
+            directives: Vec::new(),
+        }
+    }
+
+    /// The level of the directive for `target`.
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .find(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    pub fn enabled(&self, target: &str, level: log::Level) -> bool {
+        level <= self.level(target)
+    }
+
+    /// The most verbose level any target gets.
+    pub fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = LogFilterError;
+
+    /// Parses the directives, targets that none of them match are turned off.
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let mut parsed = LogFilter::new(LevelFilter::Off);
+        for directive in filter.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let parse_level = |level: &str| {
+                LevelFilter::from_str(level.trim())
+                    .map_err(|_| LogFilterError::InvalidLevel(level.trim().to_owned()))
+            };
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    let target = target.trim();
+                    if target.is_empty() {
+                        return Err(LogFilterError::EmptyTarget(directive.to_owned()));
+                    }
+                    let level = parse_level(level)?;
+                    parsed.directives.retain(|(other, _)| other != target);
+                    parsed.directives.push((target.to_owned(), level));
+                }
+                None => match parse_level(directive) {
+                    Ok(level) => parsed.default = level,
+                    Err(_) => {
+                        parsed.directives.retain(|(other, _)| other != directive);
+                        parsed
+                            .directives
+                            .push((directive.to_owned(), LevelFilter::Trace));
+                    }
+                },
+            }
+        }
+        parsed
+            .directives
+            .sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,123 @@
+//! A dock tab for browsing the [`LogRecords`].
+
+use std::collections::VecDeque;
+
+use bevy_ecs::prelude::*;
+use log::{Level, LevelFilter};
+
+use crate::dock::DockTab;
+
+use super::{LogRecord, LogRecords};
+
+/// Formats a record as a single monospace line, coloured by its level.
+pub fn record_text(record: &LogRecord) -> egui::RichText {
+    let text = egui::RichText::new(format!(
+        "{:<5} [{}] {}",
+        record.level, record.target, record.message
+    ))
+    .monospace();
+    match record.level {
+        Level::Error => text.color(egui::Color32::LIGHT_RED),
+        Level::Warn => text.color(egui::Color32::GOLD),
+        Level::Info => text,
+        Level::Debug | Level::Trace => text.weak(),
+    }
+}
+
+pub struct LogViewerTab {
+    /// Our own copy of the records, so that clearing and pausing don't affect other views.
+    records: VecDeque<LogRecord>,
+    next_index: u64,
+    level: LevelFilter,
+    /// Only shows records whose target starts with this.
+    target: String,
+    search: String,
+    paused: bool,
+}
+
+impl Default for LogViewerTab {
+    fn default() -> Self {
+        Self {
+            records: VecDeque::new(),
+            next_index: 0,
+            level: LevelFilter::Trace,
+            target: String::new(),
+            search: String::new(),
+            paused: false,
+        }
+    }
+}
+
+impl LogViewerTab {
+    fn collect(&mut self, log_records: &LogRecords) {
+        let capacity = log_records.capacity();
+        let records = &mut self.records;
+        log_records.read_since(&mut self.next_index, |record| {
+            if records.len() == capacity {
+                records.pop_front();
+            }
+            records.push_back(record.clone());
+        });
+    }
+
+    fn is_shown(&self, record: &LogRecord, search: &str) -> bool {
+        record.level <= self.level
+            && record.target.starts_with(self.target.as_str())
+            && (search.is_empty() || record.message.to_lowercase().contains(search))
+    }
+}
+
+impl DockTab for LogViewerTab {
+    const ID: &'static str = "log";
+
+    fn title(&self) -> egui::WidgetText {
+        "Log".into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, world: &mut World) {
+        if !self.paused {
+            self.collect(world.resource::<LogRecords>());
+        }
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("log_viewer_level")
+                .selected_text(self.level.as_str())
+                .show_ui(ui, |ui| {
+                    for level in LevelFilter::iter() {
+                        ui.selectable_value(&mut self.level, level, level.as_str());
+                    }
+                });
+            ui.add(
+                egui::TextEdit::singleline(&mut self.target)
+                    .hint_text("target")
+                    .desired_width(120.0),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut self.search)
+                    .hint_text("search")
+                    .desired_width(160.0),
+            );
+            ui.toggle_value(&mut self.paused, "Pause");
+            if ui.button("Clear").clicked() {
+                self.records.clear();
+            }
+        });
+        ui.separator();
+
+        let search = self.search.to_lowercase();
+        let shown: Vec<&LogRecord> = self
+            .records
+            .iter()
+            .filter(|record| self.is_shown(record, &search))
+            .collect();
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .stick_to_bottom(!self.paused)
+            .show_rows(ui, row_height, shown.len(), |ui, rows| {
+                for record in &shown[rows] {
+                    ui.add(egui::Label::new(record_text(record)).wrap(false));
+                }
+            });
+    }
+}
@@ -0,0 +1,215 @@
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_input::{keyboard::KeyCode, Input};
+use bevy_reflect::Reflect;
+use windows::Win32::UI::WindowsAndMessaging::{
+    WM_INPUT, WM_KEYFIRST, WM_KEYLAST, WM_KEYUP, WM_LBUTTONUP, WM_MBUTTONUP, WM_MOUSEFIRST,
+    WM_MOUSELAST, WM_RBUTTONUP, WM_SYSKEYUP, WM_XBUTTONUP,
+};
+
+use crate::{input::OverlayMessageHandler, ui::persistence::UiPersistence, utils};
+
+/// The [`UiPersistence`] panel key for whether the overlay is visible.
+const OVERLAY_VISIBLE_KEY: &str = "overlay.visible";
+
+/// How the overlay is shown and whether it takes input away from the game.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Resource, Reflect)]
+#[reflect(Resource)]
+pub enum OverlayState {
+    /// Nothing is drawn and the game receives all input.
+    Hidden,
+    /// The overlay is drawn, but only takes the input egui asks for.
+    #[default]
+    Passive,
+    /// The overlay is drawn and takes all mouse and keyboard input, releasing the cursor.
+    Interactive,
+}
+
+impl OverlayState {
+    pub fn is_visible(self) -> bool {
+        self != OverlayState::Hidden
+    }
+
+    /// Whether a message of `kind` should be kept from the game.
+    pub fn should_consume(
+        self,
+        kind: InputMessageKind,
+        wants_pointer_input: bool,
+        wants_keyboard_input: bool,
+    ) -> bool {
+        match self {
+            OverlayState::Hidden => false,
+            // Whatever the game saw pressed has to be seen released, or it keeps it held.
+            OverlayState::Interactive => kind != InputMessageKind::Release,
+            OverlayState::Passive => match kind {
+                InputMessageKind::Pointer => wants_pointer_input,
+                InputMessageKind::Keyboard => wants_keyboard_input,
+                InputMessageKind::Release => false,
+                // We can't tell what raw input is for without reading it.
+                InputMessageKind::Raw => wants_pointer_input || wants_keyboard_input,
+            },
+        }
+    }
+}
+
+/// The kind of game input a window message carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMessageKind {
+    Pointer,
+    Keyboard,
+    /// A key or mouse button going up, which the game always receives.
+    Release,
+    /// `WM_INPUT`, which can be either.
+    Raw,
+}
+
+impl InputMessageKind {
+    pub fn from_message(msg: u32) -> Option<Self> {
+        match msg {
+            WM_KEYUP | WM_SYSKEYUP | WM_LBUTTONUP | WM_RBUTTONUP | WM_MBUTTONUP | WM_XBUTTONUP => {
+                Some(InputMessageKind::Release)
+            }
+            WM_MOUSEFIRST..=WM_MOUSELAST => Some(InputMessageKind::Pointer),
+            WM_KEYFIRST..=WM_KEYLAST => Some(InputMessageKind::Keyboard),
+            WM_INPUT => Some(InputMessageKind::Raw),
+            _ => None,
+        }
+    }
+}
+
+/// The keys that switch between the [`OverlayState`]s.
+#[derive(Debug, Clone, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct OverlayHotkeys {
+    /// Switches between [`OverlayState::Interactive`] and [`OverlayState::Passive`].
+    pub toggle_interactive: KeyCode,
+    /// Switches between [`OverlayState::Hidden`] and [`OverlayState::Passive`].
+    pub toggle_visible: KeyCode,
+}
+
+impl Default for OverlayHotkeys {
+    fn default() -> Self {
+        Self {
+            toggle_interactive: KeyCode::Insert,
+            toggle_visible: KeyCode::Home,
+        }
+    }
+}
+
+pub fn toggle_overlay(
+    keys: Res<Input<KeyCode>>,
+    hotkeys: Res<OverlayHotkeys>,
+    mut overlay_state: ResMut<OverlayState>,
+) {
+    let next = if keys.just_pressed(hotkeys.toggle_interactive) {
+        match *overlay_state {
+            OverlayState::Interactive => OverlayState::Passive,
+            _ => OverlayState::Interactive,
+        }
+    } else if keys.just_pressed(hotkeys.toggle_visible) {
+        match *overlay_state {
+            OverlayState::Hidden => OverlayState::Passive,
+            _ => OverlayState::Hidden,
+        }
+    } else {
+        return;
+    };
+
+    log::debug!("overlay {:?} -> {:?}", *overlay_state, next);
+    *overlay_state = next;
+}
+
+/// Starts hidden if the overlay was hidden when we last uninjected.
+pub fn restore_overlay_visibility(
+    persistence: Res<UiPersistence>,
+    mut overlay_state: ResMut<OverlayState>,
+) {
+    if persistence.panel::<bool>(OVERLAY_VISIBLE_KEY) == Some(false) {
+        *overlay_state = OverlayState::Hidden;
+    }
+}
+
+pub fn persist_overlay_visibility(
+    overlay_state: Res<OverlayState>,
+    mut persistence: ResMut<UiPersistence>,
+) {
+    if overlay_state.is_changed() {
+        persistence.set_panel(OVERLAY_VISIBLE_KEY, &overlay_state.is_visible());
+    }
+}
+
+/// Keeps the cursor free and visible while the overlay is interactive.
+///
+/// NOTE: Runs in [`OverlayMessageHandler`], since the cursor display count belongs to the window thread.
+pub fn release_cursor(overlay_state: Res<OverlayState>, mut shown: Local<Option<u32>>) {
+    match (*overlay_state, *shown) {
+        (OverlayState::Interactive, _) => {
+            // The game clips the cursor again whenever it feels like it.
+            utils::unclip_cursor();
+            if shown.is_none() {
+                *shown = Some(utils::show_cursor());
+            }
+        }
+        (_, Some(count)) => {
+            utils::hide_cursor(count);
+            *shown = None;
+        }
+        _ => {}
+    }
+}
+
+pub struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<OverlayState>()
+            .register_type::<OverlayHotkeys>()
+            .init_resource::<OverlayState>()
+            .init_resource::<OverlayHotkeys>()
+            .add_systems(Startup, restore_overlay_visibility)
+            .add_systems(
+                Update,
+                (
+                    toggle_overlay,
+                    persist_overlay_visibility.after(toggle_overlay),
+                ),
+            )
+            .add_systems(OverlayMessageHandler, release_cursor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::Win32::UI::WindowsAndMessaging::{WM_KEYDOWN, WM_LBUTTONDOWN, WM_MOUSEMOVE};
+
+    use super::*;
+
+    #[test]
+    fn releases_always_reach_the_game() {
+        for msg in [WM_KEYUP, WM_SYSKEYUP, WM_LBUTTONUP, WM_XBUTTONUP] {
+            let kind = InputMessageKind::from_message(msg).unwrap();
+            assert_eq!(kind, InputMessageKind::Release);
+            assert!(!OverlayState::Interactive.should_consume(kind, true, true));
+            assert!(!OverlayState::Passive.should_consume(kind, true, true));
+        }
+    }
+
+    #[test]
+    fn interactive_takes_everything_else() {
+        for msg in [WM_KEYDOWN, WM_LBUTTONDOWN, WM_MOUSEMOVE, WM_INPUT] {
+            let kind = InputMessageKind::from_message(msg).unwrap();
+            assert!(OverlayState::Interactive.should_consume(kind, false, false));
+            assert!(!OverlayState::Hidden.should_consume(kind, true, true));
+        }
+    }
+
+    #[test]
+    fn passive_takes_what_egui_wants() {
+        let pointer = InputMessageKind::from_message(WM_LBUTTONDOWN).unwrap();
+        let keyboard = InputMessageKind::from_message(WM_KEYDOWN).unwrap();
+        assert!(OverlayState::Passive.should_consume(pointer, true, false));
+        assert!(!OverlayState::Passive.should_consume(pointer, false, true));
+        assert!(OverlayState::Passive.should_consume(keyboard, false, true));
+        assert!(!OverlayState::Passive.should_consume(keyboard, true, false));
+    }
+}
@@ -1,34 +1,78 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
+use bevy_reflect::Reflect;
+use bevy_window::{PrimaryWindow, Window};
 use egui::{LayerId, RawInput};
 
-use crate::render::{Present, RenderEvent, RenderTargetHandle, RenderTargets};
+use crate::{
+    render::{update_present_time, Present, RenderEvent, RenderTargetHandle, RenderTargets},
+    ui::fonts::Fonts,
+};
 
-// TODO: How to do layering? We need to offer a way to say "this needs to be on top of all other Paintable"
+use self::{
+    projection::ViewProjection,
+    shapes::{PaintOpacity, PaintTransform, PaintVisibility},
+    tween::TweenCompleted,
+};
 
-// TODO: Take in events that would change either and apply!
-pub fn painter_begin_frame(mut query: Query<&mut PainterContext>) {
+pub mod projection;
+pub mod shapes;
+pub mod tween;
+
+/// Starts the frame of every painter, covering the primary window at its current size.
+pub fn painter_begin_frame(
+    mut query: Query<&mut PainterContext>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+) {
+    let window = primary_window.get_single().ok();
+    let pixels_per_point = window.map_or(1.0, |window| window.scale_factor() as f32);
+    let screen_rect = window.map(|window| {
+        egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(window.width(), window.height()),
+        )
+    });
     for mut ctx in query.iter_mut() {
-        ctx.get_mut().ctx().begin_frame(RawInput::default())
+        ctx.get_mut().ctx().begin_frame(RawInput {
+            screen_rect,
+            pixels_per_point: Some(pixels_per_point),
+            ..Default::default()
+        })
     }
 }
 
+/// The order painters and uis are drawn in every frame, lower layers first and so below higher ones.
+///
+/// Painters without one are on [`PaintLayer::default`], and uis, whose layer goes on the window
+/// entity, on [`PaintLayer::UI`]. Those on the same layer are drawn in no particular order.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[reflect(Component)]
+pub struct PaintLayer(pub i32);
+
+impl PaintLayer {
+    /// Where uis are drawn by default, above painters without a layer.
+    pub const UI: Self = Self(100);
+}
+
 pub fn painter_end_frame(
     mut ev_render: EventWriter<RenderEvent>,
-    mut query: Query<&mut PainterContext>,
+    mut query: Query<(&mut PainterContext, Option<&PaintLayer>)>,
 ) {
     ev_render.send_batch(
         query
             .iter_mut()
-            .map(|mut ui_ctx| {
+            .map(|(mut ui_ctx, layer)| {
                 let ctx = ui_ctx.get_mut().ctx();
                 let out = ctx.end_frame();
-                // Use the egui contexts own tesselation, so that the ui's registered fonts exist.
+                // Use the egui contexts own tesselation, so that the registered fonts exist.
                 let primitives = ctx.tessellate(out.shapes);
+                let pixels_per_point = ctx.pixels_per_point();
                 // TODO: support repaint_after?
                 RenderEvent {
                     render_target_handle: ui_ctx.render_target_handle,
-                    primitives,
+                    layer: layer.copied().unwrap_or_default(),
+                    pixels_per_point,
+                    primitives: primitives.into(),
                     textures_delta: out.textures_delta,
                 }
             })
@@ -43,23 +87,37 @@ pub struct PaintPlugin;
 
 impl Plugin for PaintPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Present,
-            (
-                painter_begin_frame,
-                painter_end_frame.after(painter_begin_frame),
-            ),
-        )
-        .configure_set(
-            Present,
-            PainterUpdate
-                .after(painter_begin_frame)
-                .before(painter_end_frame),
-        );
+        app.init_resource::<ViewProjection>()
+            .init_resource::<Fonts>()
+            .register_type::<PaintLayer>()
+            .register_type::<PaintTransform>()
+            .register_type::<PaintVisibility>()
+            .register_type::<PaintOpacity>()
+            .add_event::<TweenCompleted>()
+            .add_systems(
+                Present,
+                (
+                    painter_begin_frame,
+                    painter_end_frame.after(painter_begin_frame),
+                    shapes::paint_shapes.in_set(PainterUpdate),
+                    projection::update_viewport.before(PainterUpdate),
+                    tween::tween_shapes
+                        .after(update_present_time)
+                        .before(PainterUpdate),
+                ),
+            )
+            .configure_set(
+                Present,
+                PainterUpdate
+                    .after(painter_begin_frame)
+                    .before(painter_end_frame),
+            );
     }
 }
 
-// TODO: Add primarypaintercontext
+/// Marker [`Component`] for the painter that shapes go to by default.
+#[derive(Default, Debug, Component, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub struct PrimaryPainterContext;
 
 // TODO: Paint has its own EVERYTHING to prevent the scheduler from serializing everything one after another...
 #[derive(Component)]
@@ -80,7 +138,7 @@ impl FromWorld for PainterContext {
 
 // Make the painter like a window, have a primary painter thats easy to get.
 // But in cases where we need a second we can have one.
-// Fonts should be shared between painters? IDK, just know that the ui has its own set of fonts.
+// Fonts are shared with the ui through `ui::fonts::Fonts`.
 
 // TODO: Make a builder thing for this?
 // TODO: FromWorld to get pixels_per_point and MAX_TEXTURE_SIDE?
@@ -94,6 +152,11 @@ impl PainterContext {
         }
     }
 
+    /// The egui context the painter paints into.
+    pub fn ctx(&self) -> &egui::Context {
+        self.painter.ctx()
+    }
+
     /// Borrows the underlying egui::Painter context mutably.
     ///
     /// When the context is queried with `&mut egui::Painter`, the Bevy scheduler is able to make
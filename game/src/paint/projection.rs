@@ -0,0 +1,403 @@
+//! Projecting world positions onto the screen, for painting markers anchored in the world.
+//!
+//! [`ViewProjection`] holds the game's view projection matrix, which has to be kept up to date by
+//! whatever reads it from the game, and [`Painter3d`] paints with it.
+
+use std::f32::consts::TAU;
+
+use bevy_ecs::prelude::*;
+use bevy_math::{Mat4, Vec2, Vec3, Vec4};
+use bevy_window::{PrimaryWindow, Window};
+use egui::{Align2, Color32, FontId, Shape, Stroke};
+
+/// How many segments a circle of a sphere wireframe is made of.
+const SPHERE_SEGMENTS: usize = 24;
+
+/// The game's camera, for mapping world positions to screen positions in points.
+///
+/// Clip space is the Direct3D one, `x` and `y` from `-w` to `w` and `z` from `0` to `w`.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct ViewProjection {
+    /// Column major, so the row major matrix of the game needs a [`Mat4::transpose`].
+    pub matrix: Mat4,
+    /// The size of the screen in points.
+    pub viewport: Vec2,
+}
+
+impl Default for ViewProjection {
+    fn default() -> Self {
+        Self {
+            matrix: Mat4::IDENTITY,
+            viewport: Vec2::ONE,
+        }
+    }
+}
+
+impl ViewProjection {
+    fn clip_position(&self, world: Vec3) -> Vec4 {
+        self.matrix * world.extend(1.0)
+    }
+
+    /// From clip space to the screen, assuming the point is in front of the camera.
+    fn clip_to_screen(&self, clip: Vec4) -> Vec2 {
+        let ndc = clip.truncate().truncate() / clip.w;
+        Vec2::new(
+            (ndc.x + 1.0) * 0.5 * self.viewport.x,
+            (1.0 - ndc.y) * 0.5 * self.viewport.y,
+        )
+    }
+
+    /// Where `world` is on the screen, `None` if it is behind the camera.
+    ///
+    /// The position can be off screen, see [`ViewProjection::is_on_screen`].
+    pub fn world_to_screen(&self, world: Vec3) -> Option<Vec2> {
+        let clip = self.clip_position(world);
+        (clip.w > f32::EPSILON).then(|| self.clip_to_screen(clip))
+    }
+
+    /// Whether `world` is inside the view frustum.
+    pub fn is_on_screen(&self, world: Vec3) -> bool {
+        let clip = self.clip_position(world);
+        frustum_planes(clip).iter().all(|distance| *distance >= 0.0)
+    }
+
+    /// The part of the line from `a` to `b` inside the view frustum, on the screen.
+    pub fn clip_line(&self, a: Vec3, b: Vec3) -> Option<(Vec2, Vec2)> {
+        let (a, b) = (self.clip_position(a), self.clip_position(b));
+        let (a_distances, b_distances) = (frustum_planes(a), frustum_planes(b));
+
+        // Liang-Barsky, in clip space where the frustum planes are flat.
+        let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+        for (a_distance, b_distance) in a_distances.into_iter().zip(b_distances) {
+            match (a_distance < 0.0, b_distance < 0.0) {
+                (true, true) => return None,
+                (false, false) => {}
+                (a_outside, _) => {
+                    let t = a_distance / (a_distance - b_distance);
+                    match a_outside {
+                        true => enter = enter.max(t),
+                        false => exit = exit.min(t),
+                    }
+                }
+            }
+        }
+        if enter > exit {
+            return None;
+        }
+        Some((
+            self.clip_to_screen(a.lerp(b, enter)),
+            self.clip_to_screen(a.lerp(b, exit)),
+        ))
+    }
+}
+
+/// How far inside each plane of the frustum a point in clip space is, negative when outside.
+fn frustum_planes(clip: Vec4) -> [f32; 6] {
+    [
+        clip.w + clip.x,
+        clip.w - clip.x,
+        clip.w + clip.y,
+        clip.w - clip.y,
+        clip.z,
+        clip.w - clip.z,
+    ]
+}
+
+fn pos2(point: Vec2) -> egui::Pos2 {
+    egui::pos2(point.x, point.y)
+}
+
+/// Painting at positions in the world, clipped to what the camera sees.
+pub trait Painter3d {
+    fn line_3d(&self, view: &ViewProjection, a: Vec3, b: Vec3, stroke: Stroke);
+
+    /// The edges of the axis aligned box from `min` to `max`.
+    fn box_3d(&self, view: &ViewProjection, min: Vec3, max: Vec3, stroke: Stroke);
+
+    /// A circle around each axis through the center.
+    fn sphere_3d(&self, view: &ViewProjection, center: Vec3, radius: f32, stroke: Stroke);
+
+    /// Text anchored at a position, only painted while the position is on screen.
+    fn text_3d(
+        &self,
+        view: &ViewProjection,
+        position: Vec3,
+        anchor: Align2,
+        text: &str,
+        font: FontId,
+        color: Color32,
+    );
+}
+
+impl Painter3d for egui::Painter {
+    fn line_3d(&self, view: &ViewProjection, a: Vec3, b: Vec3, stroke: Stroke) {
+        if let Some((a, b)) = view.clip_line(a, b) {
+            self.add(Shape::line_segment([pos2(a), pos2(b)], stroke));
+        }
+    }
+
+    fn box_3d(&self, view: &ViewProjection, min: Vec3, max: Vec3, stroke: Stroke) {
+        let corner = |i: usize| {
+            Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+        // The corners whose index differs in a single bit share an edge.
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line_3d(view, corner(i), corner(i | bit), stroke);
+                }
+            }
+        }
+    }
+
+    fn sphere_3d(&self, view: &ViewProjection, center: Vec3, radius: f32, stroke: Stroke) {
+        let circles: [fn(f32, f32) -> Vec3; 3] = [
+            |cos, sin| Vec3::new(cos, sin, 0.0),
+            |cos, sin| Vec3::new(cos, 0.0, sin),
+            |cos, sin| Vec3::new(0.0, cos, sin),
+        ];
+        for circle in circles {
+            let point = |i: usize| {
+                let angle = i as f32 / SPHERE_SEGMENTS as f32 * TAU;
+                center + circle(angle.cos(), angle.sin()) * radius
+            };
+            for i in 0..SPHERE_SEGMENTS {
+                self.line_3d(view, point(i), point(i + 1), stroke);
+            }
+        }
+    }
+
+    fn text_3d(
+        &self,
+        view: &ViewProjection,
+        position: Vec3,
+        anchor: Align2,
+        text: &str,
+        font: FontId,
+        color: Color32,
+    ) {
+        if !view.is_on_screen(position) {
+            return;
+        }
+        if let Some(screen) = view.world_to_screen(position) {
+            self.text(pos2(screen), anchor, text, font, color);
+        }
+    }
+}
+
+/// Keeps the viewport of the [`ViewProjection`] the size of the primary window.
+pub fn update_viewport(
+    mut view: ResMut<ViewProjection>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(window) = primary_window.get_single() else {
+        return;
+    };
+    let viewport = Vec2::new(window.width(), window.height());
+    if view.viewport != viewport {
+        view.viewport = viewport;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use egui::{LayerId, RawInput};
+
+    use super::*;
+
+    /// Looking down `+z` from the origin, 90 degrees wide and high, from `z = 1` to `z = 2`.
+    ///
+    /// `z` goes to `2z - 2` and `w` to `z`.
+    fn perspective() -> ViewProjection {
+        let matrix = Mat4::from_cols_array_2d(&[
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0, 1.0],
+            [0.0, 0.0, -2.0, 0.0],
+        ]);
+        ViewProjection {
+            matrix,
+            viewport: Vec2::new(200.0, 100.0),
+        }
+    }
+
+    /// `x` from -2 to 2, `y` from -1 to 1 and `z` from 0 to 10.
+    fn orthographic() -> ViewProjection {
+        let matrix = Mat4::from_cols_array_2d(&[
+            [0.5, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 0.1, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        ViewProjection {
+            matrix,
+            viewport: Vec2::new(200.0, 100.0),
+        }
+    }
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.distance(expected) < 1e-3,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn assert_line_near(actual: Option<(Vec2, Vec2)>, expected: (Vec2, Vec2)) {
+        let (a, b) = actual.expect("the line should be on screen");
+        assert_near(a, expected.0);
+        assert_near(b, expected.1);
+    }
+
+    #[test]
+    fn matrices_match_glam() {
+        let perspective_lh = Mat4::perspective_lh(FRAC_PI_2, 1.0, 1.0, 2.0);
+        assert!(perspective().matrix.abs_diff_eq(perspective_lh, 1e-6));
+        let orthographic_lh = Mat4::orthographic_lh(-2.0, 2.0, -1.0, 1.0, 0.0, 10.0);
+        assert!(orthographic().matrix.abs_diff_eq(orthographic_lh, 1e-6));
+    }
+
+    #[test]
+    fn perspective_world_to_screen() {
+        let view = perspective();
+        assert_near(
+            view.world_to_screen(Vec3::new(0.0, 0.0, 1.5)).unwrap(),
+            Vec2::new(100.0, 50.0),
+        );
+        // The top right corner of the far plane is halfway up and right at the near plane size.
+        assert_near(
+            view.world_to_screen(Vec3::new(1.0, 1.0, 2.0)).unwrap(),
+            Vec2::new(150.0, 25.0),
+        );
+        // Off screen, but still in front of the camera.
+        assert_near(
+            view.world_to_screen(Vec3::new(2.0, 0.0, 1.5)).unwrap(),
+            Vec2::new(700.0 / 3.0, 50.0),
+        );
+        assert_eq!(view.world_to_screen(Vec3::new(0.0, 0.0, -1.0)), None);
+        assert_eq!(view.world_to_screen(Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn orthographic_world_to_screen() {
+        let view = orthographic();
+        assert_near(
+            view.world_to_screen(Vec3::new(1.0, 0.5, 5.0)).unwrap(),
+            Vec2::new(150.0, 25.0),
+        );
+        assert_near(
+            view.world_to_screen(Vec3::new(-2.0, -1.0, 0.0)).unwrap(),
+            Vec2::new(0.0, 100.0),
+        );
+        // There is no behind without a perspective, only outside of the near plane.
+        assert_near(
+            view.world_to_screen(Vec3::new(0.0, 0.0, -5.0)).unwrap(),
+            Vec2::new(100.0, 50.0),
+        );
+    }
+
+    #[test]
+    fn on_screen() {
+        let view = perspective();
+        assert!(view.is_on_screen(Vec3::new(0.0, 0.0, 1.5)));
+        assert!(view.is_on_screen(Vec3::new(1.0, -1.0, 2.0)));
+        // Before the near plane, past the far plane, beside the frustum and behind the camera.
+        assert!(!view.is_on_screen(Vec3::new(0.0, 0.0, 0.5)));
+        assert!(!view.is_on_screen(Vec3::new(0.0, 0.0, 3.0)));
+        assert!(!view.is_on_screen(Vec3::new(2.0, 0.0, 1.5)));
+        assert!(!view.is_on_screen(Vec3::new(0.0, 0.0, -1.0)));
+
+        let view = orthographic();
+        assert!(view.is_on_screen(Vec3::new(1.5, 0.5, 9.0)));
+        assert!(!view.is_on_screen(Vec3::new(0.0, 0.0, -5.0)));
+        assert!(!view.is_on_screen(Vec3::new(0.0, 1.5, 5.0)));
+    }
+
+    #[test]
+    fn line_fully_inside() {
+        let view = perspective();
+        assert_line_near(
+            view.clip_line(Vec3::new(0.0, 0.0, 1.5), Vec3::new(0.5, 0.5, 1.5)),
+            (Vec2::new(100.0, 50.0), Vec2::new(400.0 / 3.0, 100.0 / 3.0)),
+        );
+    }
+
+    #[test]
+    fn line_fully_outside() {
+        let view = perspective();
+        assert_eq!(
+            view.clip_line(Vec3::new(3.0, 0.0, 1.5), Vec3::new(3.0, 1.0, 1.8)),
+            None
+        );
+        assert_eq!(
+            view.clip_line(Vec3::new(0.0, 0.0, -1.0), Vec3::new(1.0, 0.0, -2.0)),
+            None
+        );
+        // Outside of different planes at each end, passing the frustum by.
+        assert_eq!(
+            view.clip_line(Vec3::new(-3.5, 0.0, 1.5), Vec3::new(0.0, 3.5, 1.5)),
+            None
+        );
+    }
+
+    #[test]
+    fn line_crossing_the_near_plane() {
+        let view = perspective();
+        // Cut at z = 1, where the line is at x = 0.25.
+        assert_line_near(
+            view.clip_line(Vec3::new(0.25, 0.0, 1.5), Vec3::new(0.25, 0.0, 0.5)),
+            (Vec2::new(350.0 / 3.0, 50.0), Vec2::new(125.0, 50.0)),
+        );
+        // Entering from behind the camera gives the same cut, the other way around.
+        assert_line_near(
+            view.clip_line(Vec3::new(0.25, 0.0, -0.5), Vec3::new(0.25, 0.0, 1.5)),
+            (Vec2::new(125.0, 50.0), Vec2::new(350.0 / 3.0, 50.0)),
+        );
+    }
+
+    #[test]
+    fn line_crossing_a_side_plane() {
+        let view = perspective();
+        assert_line_near(
+            view.clip_line(Vec3::new(0.0, 0.0, 1.5), Vec3::new(3.0, 0.0, 1.5)),
+            (Vec2::new(100.0, 50.0), Vec2::new(200.0, 50.0)),
+        );
+        let view = orthographic();
+        assert_line_near(
+            view.clip_line(Vec3::new(0.0, 3.0, 5.0), Vec3::new(0.0, -0.5, 5.0)),
+            (Vec2::new(100.0, 0.0), Vec2::new(100.0, 75.0)),
+        );
+    }
+
+    #[test]
+    fn box_has_twelve_edges() {
+        let ctx = egui::Context::default();
+        let view = orthographic();
+        let output = ctx.run(RawInput::default(), |ctx| {
+            let painter = ctx.layer_painter(LayerId::background());
+            painter.box_3d(
+                &view,
+                Vec3::new(-1.0, -0.5, 1.0),
+                Vec3::new(1.0, 0.5, 2.0),
+                Stroke {
+                    width: 1.0,
+                    color: Color32::WHITE,
+                },
+            );
+        });
+        let edges = output
+            .shapes
+            .iter()
+            .filter(|clipped| matches!(clipped.shape, Shape::LineSegment { .. }))
+            .count();
+        assert_eq!(edges, 12);
+        assert_eq!(output.shapes.len(), 12);
+    }
+}
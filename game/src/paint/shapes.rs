@@ -0,0 +1,259 @@
+//! Shapes that stay on screen for as long as their entity exists, instead of being painted every
+//! frame by a system.
+//!
+//! An entity gets one of [`PaintRect`], [`PaintCircle`], [`PaintLine`], [`PaintText`] or
+//! [`PaintImage`], placed by its [`PaintTransform`], faded by its [`PaintOpacity`] and shown
+//! unless its [`PaintVisibility`] says otherwise. They go to the [`PrimaryPainterContext`] unless
+//! the entity has a [`PaintTarget`].
+
+use bevy_ecs::prelude::*;
+use bevy_math::Vec2;
+use bevy_reflect::Reflect;
+use bevy_utils::HashSet;
+use egui::{Align2, Color32, FontFamily, FontId, Rect, Rounding, Stroke};
+use epaint::{CircleShape, RectShape};
+
+use crate::{images::OverlayImage, ui::fonts::Fonts};
+
+use super::{PainterContext, PrimaryPainterContext};
+
+/// Where a shape is on the screen, in points.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct PaintTransform {
+    pub translation: Vec2,
+    /// Scales the size of the shape around the translation, but not its stroke width.
+    pub scale: f32,
+}
+
+impl PaintTransform {
+    pub fn from_xy(x: f32, y: f32) -> Self {
+        Self {
+            translation: Vec2::new(x, y),
+            ..Default::default()
+        }
+    }
+
+    fn apply(&self, point: Vec2) -> egui::Pos2 {
+        let point = self.translation + point * self.scale;
+        egui::pos2(point.x, point.y)
+    }
+}
+
+impl Default for PaintTransform {
+    fn default() -> Self {
+        Self {
+            translation: Vec2::ZERO,
+            scale: 1.0,
+        }
+    }
+}
+
+/// Whether a shape is painted, entities without one are.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum PaintVisibility {
+    #[default]
+    Visible,
+    Hidden,
+}
+
+/// Multiplies the opacity of all colours of a shape, entities without one are opaque.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct PaintOpacity(pub f32);
+
+impl Default for PaintOpacity {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// The painter a shape goes to, instead of the [`PrimaryPainterContext`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaintTarget(pub Entity);
+
+/// A rectangle centered on the translation.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct PaintRect {
+    pub size: Vec2,
+    pub rounding: f32,
+    pub fill: Color32,
+    pub stroke: Stroke,
+}
+
+impl Default for PaintRect {
+    fn default() -> Self {
+        Self {
+            size: Vec2::splat(10.0),
+            rounding: 0.0,
+            fill: Color32::WHITE,
+            stroke: Stroke::NONE,
+        }
+    }
+}
+
+/// A circle centered on the translation.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct PaintCircle {
+    pub radius: f32,
+    pub fill: Color32,
+    pub stroke: Stroke,
+}
+
+impl Default for PaintCircle {
+    fn default() -> Self {
+        Self {
+            radius: 5.0,
+            fill: Color32::WHITE,
+            stroke: Stroke::NONE,
+        }
+    }
+}
+
+/// A line through `points`, relative to the translation.
+#[derive(Component, Default, Debug, Clone, PartialEq)]
+pub struct PaintLine {
+    pub points: Vec<Vec2>,
+    pub stroke: Stroke,
+}
+
+/// Text anchored at the translation.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct PaintText {
+    pub text: String,
+    pub font: FontId,
+    pub color: Color32,
+    pub anchor: Align2,
+}
+
+impl Default for PaintText {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            font: FontId::default(),
+            color: Color32::WHITE,
+            anchor: Align2::LEFT_TOP,
+        }
+    }
+}
+
+/// An image centered on the translation, at its own size unless `size` is set.
+#[derive(Component, Debug, Clone)]
+pub struct PaintImage {
+    pub image: OverlayImage,
+    pub size: Option<Vec2>,
+    pub tint: Color32,
+}
+
+type AnyPaintShape<'a> = AnyOf<(
+    &'a PaintRect,
+    &'a PaintCircle,
+    &'a PaintLine,
+    &'a PaintText,
+    &'a PaintImage,
+)>;
+
+type PaintShapeItem<'a> = (
+    AnyPaintShape<'a>,
+    Option<&'a PaintTransform>,
+    Option<&'a PaintVisibility>,
+    Option<&'a PaintOpacity>,
+    Option<&'a PaintTarget>,
+);
+
+fn centered_rect(transform: &PaintTransform, size: Vec2) -> Rect {
+    Rect::from_center_size(
+        transform.apply(Vec2::ZERO),
+        egui::vec2(size.x, size.y) * transform.scale,
+    )
+}
+
+/// Paints every visible shape entity onto its painter.
+pub fn paint_shapes(
+    mut painters: Query<&mut PainterContext>,
+    primary_painter: Query<Entity, With<PrimaryPainterContext>>,
+    shapes: Query<PaintShapeItem>,
+    fonts: Res<Fonts>,
+    mut warned_families: Local<HashSet<FontFamily>>,
+) {
+    let primary_painter = primary_painter.get_single().ok();
+    for ((rect, circle, line, text, image), transform, visibility, opacity, target) in shapes.iter()
+    {
+        if visibility == Some(&PaintVisibility::Hidden) {
+            continue;
+        }
+        let Some(target) = target.map(|target| target.0).or(primary_painter) else {
+            continue;
+        };
+        let Ok(mut painter_ctx) = painters.get_mut(target) else {
+            continue;
+        };
+        let painter = painter_ctx.get_mut();
+        let transform = transform.copied().unwrap_or_default();
+        let opacity = opacity.copied().unwrap_or_default().0;
+        let fade = |color: Color32| color.gamma_multiply(opacity);
+        let fade_stroke = |stroke: Stroke| Stroke::new(stroke.width, fade(stroke.color));
+
+        if let Some(rect) = rect {
+            painter.add(RectShape::new(
+                centered_rect(&transform, rect.size),
+                Rounding::same(rect.rounding * transform.scale),
+                fade(rect.fill),
+                fade_stroke(rect.stroke),
+            ));
+        }
+        if let Some(circle) = circle {
+            painter.add(CircleShape {
+                center: transform.apply(Vec2::ZERO),
+                radius: circle.radius * transform.scale,
+                fill: fade(circle.fill),
+                stroke: fade_stroke(circle.stroke),
+            });
+        }
+        if let Some(line) = line {
+            let points = line
+                .points
+                .iter()
+                .map(|point| transform.apply(*point))
+                .collect();
+            painter.add(egui::Shape::line(points, fade_stroke(line.stroke)));
+        }
+        if let Some(text) = text {
+            // egui panics on text in a family the painter does not have.
+            let family = match fonts.has_family(&text.font.family) {
+                true => text.font.family.clone(),
+                false => {
+                    if warned_families.insert(text.font.family.clone()) {
+                        log::warn!(
+                            "font family {} is not loaded, using the proportional fonts",
+                            text.font.family
+                        );
+                    }
+                    FontFamily::Proportional
+                }
+            };
+            let font = FontId::new(text.font.size * transform.scale, family);
+            painter.text(
+                transform.apply(Vec2::ZERO),
+                text.anchor,
+                &text.text,
+                font,
+                fade(text.color),
+            );
+        }
+        if let Some(image) = image {
+            let size = image.size.unwrap_or_else(|| {
+                let [width, height] = image.image.size();
+                Vec2::new(width as f32, height as f32)
+            });
+            let uv = Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+            painter.image(
+                image.image.id(),
+                centered_rect(&transform, size),
+                uv,
+                fade(image.tint),
+            );
+        }
+    }
+}
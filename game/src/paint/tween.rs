@@ -0,0 +1,572 @@
+//! Animating the shape components, so indicators can fade, move and grow instead of popping.
+//!
+//! A [`Tween`] on a shape entity runs its steps one after another, every step moving some
+//! [`TweenValue`]s from wherever they are when the step starts to where the step says, along an
+//! [`Ease`] curve. Time advances with [`PresentTime`], so animations follow the game's frames.
+
+use std::{collections::VecDeque, f32::consts::PI, time::Duration};
+
+use bevy_ecs::prelude::*;
+use bevy_math::Vec2;
+use bevy_reflect::Reflect;
+use egui::Color32;
+
+use crate::render::PresentTime;
+
+use super::shapes::{
+    PaintCircle, PaintImage, PaintLine, PaintOpacity, PaintRect, PaintText, PaintTransform,
+};
+
+/// An easing curve, mapping the progress of a step to how far along its values are.
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Ease {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    /// Overshoots the end a little before settling on it.
+    BackOut,
+    BounceOut,
+}
+
+impl Ease {
+    /// Maps `t` from `0.0` to `1.0` onto the curve, which starts at `0.0` and ends at `1.0`.
+    ///
+    /// `t` outside of that range is clamped.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Ease::QuadInOut => match t < 0.5 {
+                true => 2.0 * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            },
+            Ease::CubicIn => t.powi(3),
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::CubicInOut => match t < 0.5 {
+                true => 4.0 * t.powi(3),
+                false => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            },
+            Ease::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Ease::SineOut => (t * PI / 2.0).sin(),
+            Ease::SineInOut => -((t * PI).cos() - 1.0) / 2.0,
+            Ease::BackOut => {
+                const OVERSHOOT: f32 = 1.70158;
+                1.0 + (OVERSHOOT + 1.0) * (t - 1.0).powi(3) + OVERSHOOT * (t - 1.0).powi(2)
+            }
+            Ease::BounceOut => bounce_out(t),
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Linear interpolation, `t` of `0.0` being `self` and `1.0` being `to`.
+pub trait Lerp {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Vec2::lerp(*self, *to, t)
+    }
+}
+
+impl Lerp for Color32 {
+    /// Per channel, premultiplied.
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        let channel = |from: u8, to: u8| (from as f32).lerp(&(to as f32), t).round() as u8;
+        Color32::from_rgba_premultiplied(
+            channel(self.r(), to.r()),
+            channel(self.g(), to.g()),
+            channel(self.b(), to.b()),
+            channel(self.a(), to.a()),
+        )
+    }
+}
+
+/// A property of a shape entity that can be animated, with the value to animate it to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TweenValue {
+    /// [`PaintTransform::translation`], needs the entity to have a [`PaintTransform`].
+    Translation(Vec2),
+    /// [`PaintTransform::scale`], needs the entity to have a [`PaintTransform`].
+    Scale(f32),
+    /// The fill of rects and circles, the stroke of lines, and the colour of text and images.
+    Color(Color32),
+    /// [`PaintOpacity`], needs the entity to have a [`PaintOpacity`].
+    Alpha(f32),
+    /// The size of rects and images, circles take the `x` as their radius.
+    Size(Vec2),
+}
+
+impl TweenValue {
+    fn lerp(&self, to: &Self, t: f32) -> Option<Self> {
+        Some(match (self, to) {
+            (Self::Translation(from), Self::Translation(to)) => Self::Translation(from.lerp(to, t)),
+            (Self::Scale(from), Self::Scale(to)) => Self::Scale(from.lerp(to, t)),
+            (Self::Color(from), Self::Color(to)) => Self::Color(from.lerp(to, t)),
+            (Self::Alpha(from), Self::Alpha(to)) => Self::Alpha(from.lerp(to, t)),
+            (Self::Size(from), Self::Size(to)) => Self::Size(from.lerp(to, t)),
+            _ => return None,
+        })
+    }
+}
+
+/// One step of a [`Tween`], animating all of its values at the same time.
+#[derive(Debug, Clone)]
+struct TweenStep {
+    to: Vec<TweenValue>,
+    duration: Duration,
+    ease: Ease,
+}
+
+/// What happens to the entity once its [`Tween`] is done.
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TweenDone {
+    /// Removes the [`Tween`], leaving the shape as it ended up.
+    #[default]
+    Remove,
+    /// Despawns the whole entity, e.g. after fading it out.
+    Despawn,
+}
+
+/// Animates the shape components of its entity, see the [module docs](self).
+#[derive(Component, Debug, Clone)]
+pub struct Tween {
+    steps: VecDeque<TweenStep>,
+    /// Where the values of the current step started, and where they go, once it started.
+    current: Option<Vec<(TweenValue, TweenValue)>>,
+    elapsed: Duration,
+    done: TweenDone,
+}
+
+impl Tween {
+    /// Animates `value` over `duration`.
+    pub fn to(value: TweenValue, duration: Duration, ease: Ease) -> Self {
+        Self {
+            steps: VecDeque::new(),
+            current: None,
+            elapsed: Duration::ZERO,
+            done: TweenDone::default(),
+        }
+        .then(value, duration, ease)
+    }
+
+    /// Animates `value` alongside the values of the last step.
+    pub fn with(mut self, value: TweenValue) -> Self {
+        match self.steps.back_mut() {
+            Some(step) => step.to.push(value),
+            None => self.steps.push_back(TweenStep {
+                to: vec![value],
+                duration: Duration::ZERO,
+                ease: Ease::Linear,
+            }),
+        }
+        self
+    }
+
+    /// Animates `value` once the last step is done.
+    pub fn then(mut self, value: TweenValue, duration: Duration, ease: Ease) -> Self {
+        self.steps.push_back(TweenStep {
+            to: vec![value],
+            duration,
+            ease,
+        });
+        self
+    }
+
+    /// Waits for `duration` once the last step is done.
+    pub fn delay(mut self, duration: Duration) -> Self {
+        self.steps.push_back(TweenStep {
+            to: Vec::new(),
+            duration,
+            ease: Ease::Linear,
+        });
+        self
+    }
+
+    pub fn on_done(mut self, done: TweenDone) -> Self {
+        self.done = done;
+        self
+    }
+}
+
+/// Sent when the [`Tween`] of an entity ran all of its steps.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TweenCompleted {
+    pub entity: Entity,
+}
+
+/// The shape components of an entity that values are read from and written to.
+struct TweenedShape<'a> {
+    transform: Option<Mut<'a, PaintTransform>>,
+    opacity: Option<Mut<'a, PaintOpacity>>,
+    rect: Option<Mut<'a, PaintRect>>,
+    circle: Option<Mut<'a, PaintCircle>>,
+    line: Option<Mut<'a, PaintLine>>,
+    text: Option<Mut<'a, PaintText>>,
+    image: Option<Mut<'a, PaintImage>>,
+}
+
+impl TweenedShape<'_> {
+    /// The colour animated by [`TweenValue::Color`], of the first shape that has one.
+    fn color(&self) -> Option<Color32> {
+        None.or(self.rect.as_ref().map(|rect| rect.fill))
+            .or(self.circle.as_ref().map(|circle| circle.fill))
+            .or(self.line.as_ref().map(|line| line.stroke.color))
+            .or(self.text.as_ref().map(|text| text.color))
+            .or(self.image.as_ref().map(|image| image.tint))
+    }
+
+    /// The current value of the same property as `value`, `None` if the entity does not have it.
+    fn read(&self, value: &TweenValue) -> Option<TweenValue> {
+        match value {
+            TweenValue::Translation(_) => self
+                .transform
+                .as_ref()
+                .map(|transform| TweenValue::Translation(transform.translation)),
+            TweenValue::Scale(_) => self
+                .transform
+                .as_ref()
+                .map(|transform| TweenValue::Scale(transform.scale)),
+            TweenValue::Color(_) => self.color().map(TweenValue::Color),
+            TweenValue::Alpha(_) => self
+                .opacity
+                .as_ref()
+                .map(|opacity| TweenValue::Alpha(opacity.0)),
+            TweenValue::Size(_) => None
+                .or(self.rect.as_ref().map(|rect| rect.size))
+                .or(self
+                    .circle
+                    .as_ref()
+                    .map(|circle| Vec2::splat(circle.radius)))
+                .or(self.image.as_ref().and_then(|image| image.size))
+                .map(TweenValue::Size),
+        }
+    }
+
+    fn write(&mut self, value: TweenValue) {
+        match value {
+            TweenValue::Translation(translation) => {
+                if let Some(transform) = &mut self.transform {
+                    transform.translation = translation;
+                }
+            }
+            TweenValue::Scale(scale) => {
+                if let Some(transform) = &mut self.transform {
+                    transform.scale = scale;
+                }
+            }
+            TweenValue::Color(color) => self.set_color(color),
+            TweenValue::Alpha(alpha) => {
+                if let Some(opacity) = &mut self.opacity {
+                    opacity.0 = alpha;
+                }
+            }
+            TweenValue::Size(size) => {
+                if let Some(rect) = &mut self.rect {
+                    rect.size = size;
+                }
+                if let Some(circle) = &mut self.circle {
+                    circle.radius = size.x;
+                }
+                if let Some(image) = &mut self.image {
+                    image.size = Some(size);
+                }
+            }
+        }
+    }
+
+    fn set_color(&mut self, color: Color32) {
+        if let Some(rect) = &mut self.rect {
+            rect.fill = color;
+        }
+        if let Some(circle) = &mut self.circle {
+            circle.fill = color;
+        }
+        if let Some(line) = &mut self.line {
+            line.stroke.color = color;
+        }
+        if let Some(text) = &mut self.text {
+            text.color = color;
+        }
+        if let Some(image) = &mut self.image {
+            image.tint = color;
+        }
+    }
+}
+
+type TweenedItem<'a> = (
+    Entity,
+    &'a mut Tween,
+    Option<&'a mut PaintTransform>,
+    Option<&'a mut PaintOpacity>,
+    Option<&'a mut PaintRect>,
+    Option<&'a mut PaintCircle>,
+    Option<&'a mut PaintLine>,
+    Option<&'a mut PaintText>,
+    Option<&'a mut PaintImage>,
+);
+
+/// Advances every [`Tween`] by the time since the last present.
+pub fn tween_shapes(
+    mut commands: Commands,
+    time: Res<PresentTime>,
+    mut ev_completed: EventWriter<TweenCompleted>,
+    mut query: Query<TweenedItem>,
+) {
+    for (entity, mut tween, transform, opacity, rect, circle, line, text, image) in query.iter_mut()
+    {
+        let mut shape = TweenedShape {
+            transform,
+            opacity,
+            rect,
+            circle,
+            line,
+            text,
+            image,
+        };
+        let tween = tween.as_mut();
+        let mut remaining = time.delta();
+
+        // A frame can finish several steps, the time left over goes to the next one.
+        while let Some(step) = tween.steps.front() {
+            let current = tween.current.get_or_insert_with(|| {
+                step.to
+                    .iter()
+                    .filter_map(|to| shape.read(to).map(|from| (from, *to)))
+                    .collect()
+            });
+            let left = step.duration.saturating_sub(tween.elapsed);
+            let finished = remaining >= left;
+            let t = match finished {
+                true => 1.0,
+                false => {
+                    tween.elapsed += remaining;
+                    tween.elapsed.as_secs_f32() / step.duration.as_secs_f32()
+                }
+            };
+            let t = step.ease.apply(t);
+            for (from, to) in current.iter() {
+                if let Some(value) = from.lerp(to, t) {
+                    shape.write(value);
+                }
+            }
+            if !finished {
+                break;
+            }
+            remaining -= left;
+            tween.steps.pop_front();
+            tween.current = None;
+            tween.elapsed = Duration::ZERO;
+        }
+
+        if tween.steps.is_empty() {
+            ev_completed.send(TweenCompleted { entity });
+            match tween.done {
+                TweenDone::Remove => {
+                    commands.entity(entity).remove::<Tween>();
+                }
+                TweenDone::Despawn => commands.entity(entity).despawn(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASES: [Ease; 12] = [
+        Ease::Linear,
+        Ease::QuadIn,
+        Ease::QuadOut,
+        Ease::QuadInOut,
+        Ease::CubicIn,
+        Ease::CubicOut,
+        Ease::CubicInOut,
+        Ease::SineIn,
+        Ease::SineOut,
+        Ease::SineInOut,
+        Ease::BackOut,
+        Ease::BounceOut,
+    ];
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn eases_start_at_zero_and_end_at_one() {
+        for ease in EASES {
+            assert_near(ease.apply(0.0), 0.0);
+            assert_near(ease.apply(1.0), 1.0);
+            assert_eq!(ease.apply(-1.0), ease.apply(0.0), "{:?}", ease);
+            assert_eq!(ease.apply(2.0), ease.apply(1.0), "{:?}", ease);
+        }
+        assert_eq!(Ease::QuadIn.apply(0.5), 0.25);
+        assert_eq!(Ease::QuadInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn back_out_overshoots() {
+        // 1 + 2.70158 * -0.4^3 + 1.70158 * -0.4^2
+        assert_near(Ease::BackOut.apply(0.6), 1.0993517);
+        let max = (0..=100)
+            .map(|i| Ease::BackOut.apply(i as f32 / 100.0))
+            .fold(f32::MIN, f32::max);
+        assert!(max > 1.09, "{}", max);
+    }
+
+    #[test]
+    fn colors_lerp_per_channel() {
+        assert_eq!(
+            Color32::BLACK.lerp(&Color32::WHITE, 0.5),
+            Color32::from_gray(128)
+        );
+        let to = Color32::from_rgba_premultiplied(100, 50, 0, 200);
+        assert_eq!(
+            Color32::TRANSPARENT.lerp(&to, 0.25),
+            Color32::from_rgba_premultiplied(25, 13, 0, 50)
+        );
+        assert_eq!(Color32::TRANSPARENT.lerp(&to, 0.0), Color32::TRANSPARENT);
+        assert_eq!(Color32::TRANSPARENT.lerp(&to, 1.0), to);
+    }
+
+    fn world() -> (World, Schedule) {
+        let mut world = World::new();
+        world.init_resource::<Events<TweenCompleted>>();
+        let mut schedule = Schedule::new();
+        schedule.add_systems(tween_shapes);
+        (world, schedule)
+    }
+
+    fn run_frame(world: &mut World, schedule: &mut Schedule, millis: u64) {
+        world.insert_resource(PresentTime::from_delta(Duration::from_millis(millis)));
+        schedule.run(world);
+    }
+
+    fn completed(world: &mut World) -> Vec<Entity> {
+        world
+            .resource_mut::<Events<TweenCompleted>>()
+            .drain()
+            .map(|ev| ev.entity)
+            .collect()
+    }
+
+    #[test]
+    fn steps_carry_the_time_left_over() {
+        let (mut world, mut schedule) = world();
+        let millis = Duration::from_millis;
+        let entity = world
+            .spawn((
+                PaintTransform::default(),
+                PaintOpacity::default(),
+                Tween::to(TweenValue::Alpha(0.5), millis(100), Ease::Linear)
+                    .then(TweenValue::Alpha(0.0), millis(100), Ease::Linear)
+                    .then(TweenValue::Scale(2.0), millis(100), Ease::Linear),
+            ))
+            .id();
+        let scale = |world: &World| world.get::<PaintTransform>(entity).unwrap().scale;
+
+        // Finishes the first two steps, and is halfway through the third.
+        run_frame(&mut world, &mut schedule, 250);
+        assert_near(world.get::<PaintOpacity>(entity).unwrap().0, 0.0);
+        assert_near(scale(&world), 1.5);
+        assert!(completed(&mut world).is_empty());
+
+        run_frame(&mut world, &mut schedule, 25);
+        assert_near(scale(&world), 1.75);
+
+        run_frame(&mut world, &mut schedule, 25);
+        assert_near(scale(&world), 2.0);
+        assert_eq!(completed(&mut world), [entity]);
+        // Removed, leaving the shape as it ended up.
+        assert!(world.get::<Tween>(entity).is_none());
+        assert_near(scale(&world), 2.0);
+    }
+
+    #[test]
+    fn delays_carry_the_time_left_over() {
+        let (mut world, mut schedule) = world();
+        let entity = world
+            .spawn((
+                PaintOpacity::default(),
+                Tween::to(
+                    TweenValue::Alpha(0.0),
+                    Duration::from_millis(100),
+                    Ease::Linear,
+                )
+                .delay(Duration::from_millis(100))
+                .then(
+                    TweenValue::Alpha(1.0),
+                    Duration::from_millis(100),
+                    Ease::QuadIn,
+                ),
+            ))
+            .id();
+
+        run_frame(&mut world, &mut schedule, 250);
+        assert_near(world.get::<PaintOpacity>(entity).unwrap().0, 0.25);
+    }
+
+    #[test]
+    fn despawns_when_done() {
+        let (mut world, mut schedule) = world();
+        let entity = world
+            .spawn((
+                PaintOpacity::default(),
+                Tween::to(
+                    TweenValue::Alpha(0.0),
+                    Duration::from_millis(100),
+                    Ease::Linear,
+                )
+                .on_done(TweenDone::Despawn),
+            ))
+            .id();
+
+        run_frame(&mut world, &mut schedule, 60);
+        assert!(world.get_entity(entity).is_some());
+        assert!(completed(&mut world).is_empty());
+
+        run_frame(&mut world, &mut schedule, 60);
+        assert_eq!(completed(&mut world), [entity]);
+        assert!(world.get_entity(entity).is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
+    console::ConsoleAppExt,
+    dock::{DockAppExt, DockTab},
     render::Present,
-    ui::{self, UiContext},
 };
 use bevy_app::{App, Plugin};
 use bevy_ecs::prelude::*;
@@ -11,8 +12,19 @@ fn mark_new_profiled_frame() {
     puffin::GlobalProfiler::lock().new_frame()
 }
 
-fn profiler_ui(mut ui_ctx: ResMut<UiContext>) {
-    puffin_egui::profiler_window(&ui_ctx.get_mut());
+#[derive(Default)]
+struct ProfilerTab;
+
+impl DockTab for ProfilerTab {
+    const ID: &'static str = "profiler";
+
+    fn title(&self) -> egui::WidgetText {
+        "Profiler".into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _world: &mut World) {
+        puffin_egui::profiler_ui(ui);
+    }
 }
 
 /// Marker [`Component`] for the profilers ui context.
@@ -21,13 +33,13 @@ pub struct ProfilerUiContext;
 
 impl Plugin for ProfilerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Present,
-            (
-                mark_new_profiled_frame,
-                profiler_ui.after(ui::ui_begin_frame),
-            ),
-        );
+        app.add_dock_tab::<ProfilerTab>()
+            .register_command(
+                "profiler",
+                "Turns the profiler scopes on or off",
+                |In((on,)): In<(bool,)>| puffin::set_scopes_on(on),
+            )
+            .add_systems(Present, mark_new_profiled_frame);
     }
 
     fn finish(&self, app: &mut App) {
@@ -1,7 +1,13 @@
+use std::{
+    any::Any,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 use bevy_utils::HashMap;
 use binsig::Pattern;
 use windows::{
-    core::*,
+    core::{Interface, HRESULT},
     Win32::Graphics::Dxgi::{Common::DXGI_FORMAT, IDXGISwapChain},
 };
 
@@ -15,9 +21,20 @@ use bevy_schedule_hook::{
     Detours,
 };
 
+use epaint::{textures::TexturesDelta, ImageData, ImageDelta, TextureId};
 use epaint_dx11::DirectX11Renderer;
 
-use crate::utils::{get_module, module_addr, module_to_bytes};
+use crate::{
+    images::OverlayImages,
+    overlay::OverlayState,
+    paint::PaintLayer,
+    utils::{get_module, module_addr, module_to_bytes},
+};
+
+use self::renderer::{OverlayRenderer, RenderError};
+
+pub mod renderer;
+
 /// The schedule that assumes the role of present.
 ///
 /// NOTE: This is a dispatcher.
@@ -78,20 +95,137 @@ fn resize_buffers_original(
     );
 }
 
-fn setup_render_targets(input: NonSend<PresentInput>, mut render_targets: ResMut<RenderTargets>) {
-    let swapchain = &input.__arg_0;
-    render_targets.setup_renderers_from_swapchain(swapchain);
+/// Sets up the renderers that are missing, which have none of the textures of the old ones.
+///
+/// [`RenderTargets`] sends them the textures egui manages again, and [`OverlayImages`] its images.
+fn setup_render_targets(
+    input: NonSend<PresentInput>,
+    mut render_targets: ResMut<RenderTargets>,
+    images: Option<ResMut<OverlayImages>>,
+) {
+    // SAFETY: The swapchain is being presented, and is the one every renderer is created from
+    // since the game only has the one.
+    let initialized = unsafe { render_targets.setup_renderers(&input.__arg_0) };
+    if let Some(mut images) = images {
+        for render_target_handle in initialized {
+            images.forget_uploads(render_target_handle);
+        }
+    }
 }
 
 #[derive(Component, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
 pub struct RenderTargetHandle(usize);
 
-#[derive(Resource, Default)]
-pub struct RenderTargets {
-    renderers: HashMap<RenderTargetHandle, Option<DirectX11Renderer>>,
+/// A renderer of a `Target`, boxed so it can be any [`OverlayRenderer`].
+type BoxedRenderer<Target> = Box<dyn OverlayRenderer<Target>>;
+
+/// # Safety
+/// See [`OverlayRenderer::init_from_target`].
+unsafe fn init_boxed_renderer<Target, R: OverlayRenderer<Target> + 'static>(
+    target: &Target,
+) -> Result<BoxedRenderer<Target>, RenderError> {
+    Ok(Box::new(R::init_from_target(target)?))
+}
+
+/// Copies `patch` into `image` at `pos`, as a renderer does with a partial [`ImageDelta`].
+fn patch_image(image: &mut ImageData, patch: &ImageData, pos: [usize; 2]) {
+    fn copy_rows<T: Copy>(
+        pixels: &mut [T],
+        width: usize,
+        patch: &[T],
+        patch_width: usize,
+        [x, y]: [usize; 2],
+    ) {
+        for (row, patch_row) in patch.chunks_exact(patch_width).enumerate() {
+            let start = (y + row) * width + x;
+            pixels[start..start + patch_width].copy_from_slice(patch_row);
+        }
+    }
+
+    match (image, patch) {
+        (ImageData::Color(image), ImageData::Color(patch)) => {
+            let image = Arc::make_mut(image);
+            copy_rows(
+                &mut image.pixels,
+                image.size[0],
+                &patch.pixels,
+                patch.size[0],
+                pos,
+            );
+        }
+        (ImageData::Font(image), ImageData::Font(patch)) => {
+            copy_rows(
+                &mut image.pixels,
+                image.size[0],
+                &patch.pixels,
+                patch.size[0],
+                pos,
+            );
+        }
+        _ => log::warn!("ignored a texture patch of another kind than its texture"),
+    }
 }
 
-impl RenderTargets {
+/// The egui managed textures sent to a render target, as the full images they are now.
+///
+/// egui sends a texture once and then only patches it, so a new renderer gets these instead.
+#[derive(Default)]
+struct ManagedTextures {
+    images: HashMap<TextureId, ImageDelta>,
+    /// Whether the renderer is new, and has none of them.
+    resend: bool,
+}
+
+impl ManagedTextures {
+    fn update(&mut self, textures_delta: &TexturesDelta) {
+        for (id, delta) in &textures_delta.set {
+            if !matches!(id, TextureId::Managed(_)) {
+                continue;
+            }
+            match delta.pos {
+                None => {
+                    self.images.insert(*id, delta.clone());
+                }
+                Some(pos) => {
+                    if let Some(full) = self.images.get_mut(id) {
+                        patch_image(&mut full.image, &delta.image, pos);
+                    }
+                }
+            }
+        }
+        for id in &textures_delta.free {
+            self.images.remove(id);
+        }
+    }
+}
+
+/// The renderers of the render targets, which all paint to the `Target` the game presents.
+///
+/// The game presents an [`IDXGISwapChain`], another graphics API would use its own `Target`.
+#[derive(Resource)]
+pub struct RenderTargets<Target: 'static = IDXGISwapChain> {
+    renderers: HashMap<RenderTargetHandle, Option<BoxedRenderer<Target>>>,
+    managed_textures: HashMap<RenderTargetHandle, ManagedTextures>,
+    /// Sets up the renderers once the target is known.
+    init_renderer: unsafe fn(&Target) -> Result<BoxedRenderer<Target>, RenderError>,
+}
+
+impl Default for RenderTargets<IDXGISwapChain> {
+    fn default() -> Self {
+        Self::with_renderer::<DirectX11Renderer>()
+    }
+}
+
+impl<Target: 'static> RenderTargets<Target> {
+    /// Render targets painted by `R`.
+    pub fn with_renderer<R: OverlayRenderer<Target> + 'static>() -> Self {
+        Self {
+            renderers: HashMap::default(),
+            managed_textures: HashMap::default(),
+            init_renderer: init_boxed_renderer::<Target, R>,
+        }
+    }
+
     pub fn create_uninitialized_render_target(&mut self) -> RenderTargetHandle {
         let handle: RenderTargetHandle = RenderTargetHandle(self.renderers.len());
         log::debug!("created render target with handle {}", handle.0);
@@ -99,69 +233,311 @@ impl RenderTargets {
         handle
     }
 
-    pub fn from_handle(&mut self, handle: RenderTargetHandle) -> Option<&mut DirectX11Renderer> {
+    pub fn from_handle(
+        &mut self,
+        handle: RenderTargetHandle,
+    ) -> Option<&mut dyn OverlayRenderer<Target>> {
         match self.renderers.get_mut(&handle)? {
-            Some(r) => Some(r),
+            Some(r) => Some(r.as_mut()),
             None => None,
         }
     }
 
-    pub fn setup_renderers_from_swapchain(&mut self, swapchain: &IDXGISwapChain) {
+    /// Saves the state of the game with any renderer, `None` while there are none to paint with.
+    ///
+    /// # Safety
+    /// See [`OverlayRenderer::save_state`].
+    pub unsafe fn save_state(&self, target: &Target) -> Option<Result<Box<dyn Any>, RenderError>> {
+        let renderer = self.renderers.values().flatten().next()?;
+        Some(renderer.save_state(target))
+    }
+
+    pub fn release_render_targets(&mut self) {
         self.renderers
-            .iter_mut()
-            .filter(|(_, r)| r.is_none())
-            .for_each(|(_, r)| {
-                *r = Some(unsafe {
-                    epaint_dx11::DirectX11Renderer::init_from_swapchain(swapchain)
-                        .expect("failed to create renderer from swapchain")
-                })
-            })
+            .values_mut()
+            .flatten()
+            .for_each(|r| r.release_target());
+    }
+
+    /// Points the renderers at the resized buffers of the target.
+    ///
+    /// A renderer that fails to is dropped, to be set up again on the next present.
+    ///
+    /// # Safety
+    /// See [`OverlayRenderer::resize_target`].
+    pub unsafe fn recreate_render_targets(&mut self, target: &Target) {
+        for (handle, renderer) in self.renderers.iter_mut() {
+            let Some(r) = renderer else {
+                continue;
+            };
+            if let Err(err) = r.resize_target(target) {
+                log::error!(
+                    "failed to recreate render target {} after resize: {}",
+                    handle.0,
+                    err
+                );
+                *renderer = None;
+            }
+        }
+    }
+
+    /// Sets up the renderers that are missing, returning the render targets that got one.
+    ///
+    /// A renderer that fails to is tried again on the next present.
+    ///
+    /// # Safety
+    /// See [`OverlayRenderer::init_from_target`], every renderer has to be created from the same
+    /// target.
+    pub unsafe fn setup_renderers(&mut self, target: &Target) -> Vec<RenderTargetHandle> {
+        let init_renderer = self.init_renderer;
+        let mut initialized = Vec::new();
+        for (handle, renderer) in self.renderers.iter_mut() {
+            if renderer.is_some() {
+                continue;
+            }
+            match init_renderer(target) {
+                Ok(r) => {
+                    *renderer = Some(r);
+                    self.managed_textures.entry(*handle).or_default().resend = true;
+                    initialized.push(*handle);
+                }
+                Err(err) => log::error!(
+                    "failed to create renderer {} from target: {}",
+                    handle.0,
+                    err
+                ),
+            }
+        }
+        initialized
+    }
+
+    /// Keeps track of the egui managed textures `textures_delta` sends to `handle`.
+    fn update_managed_textures(
+        &mut self,
+        handle: RenderTargetHandle,
+        textures_delta: &TexturesDelta,
+    ) {
+        self.managed_textures
+            .entry(handle)
+            .or_default()
+            .update(textures_delta);
+    }
+
+    /// Keeps track of the egui managed textures `textures_delta` sends to `handle`, returning what
+    /// its renderer has to update, which is all of the managed textures if it was just set up.
+    fn managed_textures_delta(
+        &mut self,
+        handle: RenderTargetHandle,
+        textures_delta: TexturesDelta,
+    ) -> TexturesDelta {
+        self.update_managed_textures(handle, &textures_delta);
+        let managed = self.managed_textures.entry(handle).or_default();
+        let has_renderer = matches!(self.renderers.get(&handle), Some(Some(_)));
+        if !managed.resend || !has_renderer {
+            return textures_delta;
+        }
+        managed.resend = false;
+
+        // The managed textures are already up to date with `textures_delta`.
+        let is_managed = |id: &TextureId| matches!(id, TextureId::Managed(_));
+        let set = managed
+            .images
+            .iter()
+            .map(|(id, delta)| (*id, delta.clone()));
+        TexturesDelta {
+            set: set
+                .chain(
+                    textures_delta
+                        .set
+                        .into_iter()
+                        .filter(|(id, _)| !is_managed(id)),
+                )
+                .collect(),
+            free: textures_delta
+                .free
+                .into_iter()
+                .filter(|id| !is_managed(id))
+                .collect(),
+        }
     }
 }
 
+/// The time between the last two presents, for animating by the frames of the game.
+#[derive(Resource, Default, Debug)]
+pub struct PresentTime {
+    last: Option<Instant>,
+    delta: Duration,
+}
+
+impl PresentTime {
+    /// Zero on the first present.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// A frame that took `delta`, without presenting.
+    #[cfg(test)]
+    pub(crate) fn from_delta(delta: Duration) -> Self {
+        Self {
+            last: Some(Instant::now()),
+            delta,
+        }
+    }
+}
+
+pub fn update_present_time(mut time: ResMut<PresentTime>) {
+    let now = Instant::now();
+    time.delta = time.last.map_or(Duration::ZERO, |last| now - last);
+    time.last = Some(now);
+}
+
 #[derive(Event)]
 pub struct RenderEvent {
     // TODO: Window handle as well...
     // pub window_handle: WindowRef,
     pub render_target_handle: RenderTargetHandle,
-    pub primitives: Vec<epaint::ClippedPrimitive>,
-    pub textures_delta: epaint::textures::TexturesDelta,
+    /// Events are rendered from the lowest layer up.
+    pub layer: PaintLayer,
+    /// The scale the primitives were tessellated at, used to convert them from points to pixels.
+    pub pixels_per_point: f32,
+    /// Shared, so primitives that did not change can be presented again without copying them.
+    pub primitives: Arc<Vec<epaint::ClippedPrimitive>>,
+    pub textures_delta: TexturesDelta,
 }
 
 // TODO: register an event when the pixel_per_point changes to update text shapes.
 
-fn resize_buffers_update_render(
+/// Lets go of the back buffers, `ResizeBuffers` fails while anything references them.
+fn release_render_targets(mut render_targets: ResMut<RenderTargets>) {
+    render_targets.release_render_targets();
+}
+
+fn recreate_render_targets(
     mut render_targets: ResMut<RenderTargets>,
     input: NonSend<ResizeBuffersInput>,
 ) {
-    // let mut render_target = match render_target {
-    //     Some(r) => r,
-    //     None => return,
-    // };
+    // SAFETY: The swapchain is being resized, and is the one the renderers were created from.
+    unsafe { render_targets.recreate_render_targets(&input.__arg_0) };
+}
+
+/// The render events of a frame for one render target on one layer, painted together.
+struct RenderBatch {
+    render_target_handle: RenderTargetHandle,
+    layer: PaintLayer,
+    pixels_per_point: f32,
+    /// The primitives of each event, in the order they were sent.
+    primitives: Vec<Arc<Vec<epaint::ClippedPrimitive>>>,
+    textures_delta: TexturesDelta,
+}
+
+/// Merges the events by layer and render target, from the lowest layer up.
+///
+/// Events of a batch keep the order they were sent in, and only events tessellated at the same
+/// scale are merged.
+fn batch_render_events(events: impl Iterator<Item = RenderEvent>) -> Vec<RenderBatch> {
+    let mut events: Vec<RenderEvent> = events.collect();
+    // Stable, so events in the same batch keep the order they were sent in.
+    events.sort_by_key(|re| (re.layer, re.render_target_handle));
+
+    let mut batches: Vec<RenderBatch> = Vec::new();
+    for re in events {
+        match batches.last_mut() {
+            Some(batch)
+                if batch.layer == re.layer
+                    && batch.render_target_handle == re.render_target_handle
+                    && batch.pixels_per_point == re.pixels_per_point =>
+            {
+                batch.primitives.push(re.primitives);
+                batch.textures_delta.append(re.textures_delta);
+            }
+            _ => batches.push(RenderBatch {
+                render_target_handle: re.render_target_handle,
+                layer: re.layer,
+                pixels_per_point: re.pixels_per_point,
+                primitives: vec![re.primitives],
+                textures_delta: re.textures_delta,
+            }),
+        }
+    }
+    batches
+}
 
-    // render_target.on_resize_buffers(&input.__arg_0, original)
+/// Paints the batches, saving and restoring the state of the game once around all of them.
+///
+/// Textures are still updated while the overlay is not `visible`, since egui only sends them once.
+///
+/// # Safety
+/// `target` has to be alive, and be the one the renderers were created from.
+unsafe fn paint_batches<Target>(
+    render_targets: &mut RenderTargets<Target>,
+    target: &Target,
+    batches: Vec<RenderBatch>,
+    visible: bool,
+    mut images: Option<&mut OverlayImages>,
+) {
+    if batches.is_empty() {
+        return;
+    }
+    let Some(state) = render_targets.save_state(target) else {
+        // Still keep track of the textures, for the renderers once they are set up.
+        for batch in batches {
+            render_targets
+                .update_managed_textures(batch.render_target_handle, &batch.textures_delta);
+        }
+        return;
+    };
+    let _state = state.expect("failed to save the device state");
+    for mut batch in batches {
+        puffin::profile_scope!(
+            "paint_batch",
+            format!(
+                "target {} layer {}",
+                batch.render_target_handle.0, batch.layer.0
+            )
+        );
+        let mut textures_delta =
+            render_targets.managed_textures_delta(batch.render_target_handle, batch.textures_delta);
+        let Some(render_target) = render_targets.from_handle(batch.render_target_handle) else {
+            continue;
+        };
+        if !visible {
+            batch.primitives.clear();
+        }
+        if let Some(images) = images.as_mut() {
+            textures_delta.append(images.textures_delta(batch.render_target_handle));
+        }
+        render_target
+            .update_textures(target, textures_delta)
+            .expect("update_textures failed");
+        let primitives: Vec<&epaint::ClippedPrimitive> =
+            batch.primitives.iter().flat_map(|p| p.iter()).collect();
+        render_target
+            .paint_primitives(target, batch.pixels_per_point, &primitives)
+            .expect("paint_primitives failed");
+    }
 }
 
+/// Paints the render events of the frame to the swapchain being presented.
 pub fn present_render_primitives(
     mut render_targets: ResMut<RenderTargets>,
     input: NonSend<PresentInput>,
-    mut ev_render: EventReader<RenderEvent>,
+    overlay_state: Res<OverlayState>,
+    mut images: Option<ResMut<OverlayImages>>,
+    mut render_events: ResMut<Events<RenderEvent>>,
 ) {
-    for re in ev_render.iter() {
-        if let Some(render_target) = render_targets.from_handle(re.render_target_handle) {
-            unsafe {
-                render_target
-                    .paint_primitives(
-                        &input.__arg_0,
-                        (1920.0, 1080.0),
-                        // TODO: Cloning here :skull:
-                        re.textures_delta.clone(),
-                        re.primitives.clone(),
-                    )
-                    .expect("paint_primitives failed")
-            }
-        }
-    }
+    puffin::profile_function!();
+
+    let batches = batch_render_events(render_events.drain());
+    // SAFETY: The swapchain is being presented, and is the one the renderers were created from.
+    unsafe {
+        paint_batches(
+            &mut render_targets,
+            &input.__arg_0,
+            batches,
+            overlay_state.is_visible(),
+            images.as_deref_mut(),
+        )
+    };
 }
 
 pub struct RenderPlugin;
@@ -172,6 +548,7 @@ impl Plugin for RenderPlugin {
         let mut present_sched = Schedule::new();
         present_sched.set_executor_kind(bevy_ecs::schedule::ExecutorKind::MultiThreaded);
         app.init_resource::<RenderTargets>()
+            .init_resource::<PresentTime>()
             .init_schedule(Present)
             .init_schedule(ResizeBuffers)
             .add_event::<RenderEvent>()
@@ -181,6 +558,7 @@ impl Plugin for RenderPlugin {
                 Present,
                 (
                     hook_resize_buffers.run_if(run_once()),
+                    update_present_time,
                     setup_render_targets.before(present_render_primitives),
                     present_render_primitives.before(present_original),
                     present_original,
@@ -188,7 +566,11 @@ impl Plugin for RenderPlugin {
             )
             .add_systems(
                 ResizeBuffers,
-                (resize_buffers_original, resize_buffers_update_render),
+                (
+                    release_render_targets.before(resize_buffers_original),
+                    resize_buffers_original,
+                    recreate_render_targets.after(resize_buffers_original),
+                ),
             );
     }
 }
@@ -201,3 +583,421 @@ fn find_present_fn() -> Option<PresentFn> {
         .next()
         .map(|(offset, _)| unsafe { std::mem::transmute(module_addr(module) + offset) })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    use egui::{Color32, ColorImage, Rect, TextureOptions};
+    use epaint::{ClippedPrimitive, Mesh, Primitive};
+    use epaint_dx11::DirectX11Error;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Call {
+        Init,
+        SaveState,
+        RestoreState,
+        UpdateTextures {
+            renderer: usize,
+            set: Vec<TextureId>,
+        },
+        Paint {
+            renderer: usize,
+            pixels_per_point: f32,
+            primitives: Vec<TextureId>,
+        },
+    }
+
+    /// Stands in for the swapchain, recording what the renderers do to it.
+    #[derive(Default)]
+    struct RecordingTarget {
+        calls: Arc<Mutex<Vec<Call>>>,
+        /// How many renderers were created from it.
+        renderers: AtomicUsize,
+        /// The renderer that fails to pick the target back up after a resize.
+        fail_resize: Option<usize>,
+    }
+
+    impl RecordingTarget {
+        fn record(&self, call: Call) {
+            self.calls.lock().unwrap().push(call);
+        }
+
+        fn take_calls(&self) -> Vec<Call> {
+            std::mem::take(&mut self.calls.lock().unwrap())
+        }
+    }
+
+    /// Records the state being given back once dropped.
+    struct RecordingState(Arc<Mutex<Vec<Call>>>);
+
+    impl Drop for RecordingState {
+        fn drop(&mut self) {
+            self.0.lock().unwrap().push(Call::RestoreState);
+        }
+    }
+
+    /// Numbered in the order the renderers were created in.
+    struct RecordingRenderer {
+        id: usize,
+    }
+
+    impl OverlayRenderer<RecordingTarget> for RecordingRenderer {
+        unsafe fn init_from_target(target: &RecordingTarget) -> Result<Self, RenderError> {
+            target.record(Call::Init);
+            Ok(Self {
+                id: target.renderers.fetch_add(1, Ordering::Relaxed),
+            })
+        }
+
+        unsafe fn save_state(&self, target: &RecordingTarget) -> Result<Box<dyn Any>, RenderError> {
+            target.record(Call::SaveState);
+            Ok(Box::new(RecordingState(target.calls.clone())))
+        }
+
+        unsafe fn update_textures(
+            &mut self,
+            target: &RecordingTarget,
+            textures_delta: TexturesDelta,
+        ) -> Result<(), RenderError> {
+            target.record(Call::UpdateTextures {
+                renderer: self.id,
+                set: textures_delta.set.iter().map(|(id, _)| *id).collect(),
+            });
+            Ok(())
+        }
+
+        unsafe fn paint_primitives(
+            &mut self,
+            target: &RecordingTarget,
+            pixels_per_point: f32,
+            primitives: &[&ClippedPrimitive],
+        ) -> Result<(), RenderError> {
+            let primitives = primitives
+                .iter()
+                .map(|clipped| match &clipped.primitive {
+                    Primitive::Mesh(mesh) => mesh.texture_id,
+                    Primitive::Callback(_) => unreachable!(),
+                })
+                .collect();
+            target.record(Call::Paint {
+                renderer: self.id,
+                pixels_per_point,
+                primitives,
+            });
+            Ok(())
+        }
+
+        fn release_target(&mut self) {}
+
+        unsafe fn resize_target(&mut self, target: &RecordingTarget) -> Result<(), RenderError> {
+            if target.fail_resize == Some(self.id) {
+                return Err(DirectX11Error::General("resize failed").into());
+            }
+            Ok(())
+        }
+    }
+
+    fn ids(tags: &[u64]) -> Vec<TextureId> {
+        tags.iter().copied().map(TextureId::User).collect()
+    }
+
+    /// An event whose primitives are told apart by their texture.
+    fn event(
+        render_target_handle: RenderTargetHandle,
+        layer: i32,
+        pixels_per_point: f32,
+        primitives: &[u64],
+        textures: &[u64],
+    ) -> RenderEvent {
+        let primitives = ids(primitives)
+            .into_iter()
+            .map(|id| ClippedPrimitive {
+                clip_rect: Rect::EVERYTHING,
+                primitive: Primitive::Mesh(Mesh::with_texture(id)),
+            })
+            .collect();
+        let image = ColorImage::new([1, 1], Color32::WHITE);
+        let textures_delta = TexturesDelta {
+            set: ids(textures)
+                .into_iter()
+                .map(|id| {
+                    (
+                        id,
+                        ImageDelta::full(image.clone(), TextureOptions::default()),
+                    )
+                })
+                .collect(),
+            free: Vec::new(),
+        };
+        RenderEvent {
+            render_target_handle,
+            layer: PaintLayer(layer),
+            pixels_per_point,
+            primitives: Arc::new(primitives),
+            textures_delta,
+        }
+    }
+
+    /// Events to two render targets, sent out of order.
+    fn events() -> Vec<RenderEvent> {
+        let (h0, h1) = (RenderTargetHandle(0), RenderTargetHandle(1));
+        vec![
+            event(h1, 0, 1.0, &[1], &[10]),
+            event(h0, 100, 1.0, &[2], &[]),
+            event(h0, 0, 1.0, &[3], &[]),
+            event(h1, 0, 1.0, &[4], &[11]),
+            event(h0, 0, 2.0, &[5], &[]),
+        ]
+    }
+
+    #[test]
+    fn events_are_batched_by_layer_and_target() {
+        let batches: Vec<_> = batch_render_events(events().into_iter())
+            .into_iter()
+            .map(|batch| {
+                let primitives: Vec<TextureId> = batch
+                    .primitives
+                    .iter()
+                    .flat_map(|primitives| primitives.iter())
+                    .map(|clipped| match &clipped.primitive {
+                        Primitive::Mesh(mesh) => mesh.texture_id,
+                        Primitive::Callback(_) => unreachable!(),
+                    })
+                    .collect();
+                let textures: Vec<TextureId> =
+                    batch.textures_delta.set.iter().map(|(id, _)| *id).collect();
+                (
+                    batch.layer.0,
+                    batch.render_target_handle.0,
+                    batch.pixels_per_point,
+                    primitives,
+                    textures,
+                )
+            })
+            .collect();
+        assert_eq!(
+            batches,
+            [
+                (0, 0, 1.0, ids(&[3]), ids(&[])),
+                // Tessellated at another scale, so not merged.
+                (0, 0, 2.0, ids(&[5]), ids(&[])),
+                (0, 1, 1.0, ids(&[1, 4]), ids(&[10, 11])),
+                (100, 0, 1.0, ids(&[2]), ids(&[])),
+            ]
+        );
+    }
+
+    /// Render targets 0 and 1, painted by renderers 0 and 1.
+    fn render_targets(target: &RecordingTarget) -> RenderTargets<RecordingTarget> {
+        let mut render_targets = RenderTargets::with_renderer::<RecordingRenderer>();
+        for expected in [RenderTargetHandle(0), RenderTargetHandle(1)] {
+            let handle = render_targets.create_uninitialized_render_target();
+            assert_eq!(handle, expected);
+            assert_eq!(unsafe { render_targets.setup_renderers(target) }, [handle]);
+        }
+        assert_eq!(unsafe { render_targets.setup_renderers(target) }, []);
+        assert_eq!(target.take_calls(), [Call::Init, Call::Init]);
+        render_targets
+    }
+
+    #[test]
+    fn batches_are_painted_in_order_with_one_save() {
+        let target = RecordingTarget::default();
+        let mut render_targets = render_targets(&target);
+
+        let batches = batch_render_events(events().into_iter());
+        unsafe { paint_batches(&mut render_targets, &target, batches, true, None) };
+
+        let update = |renderer, set: &[u64]| Call::UpdateTextures {
+            renderer,
+            set: ids(set),
+        };
+        let paint = |renderer, pixels_per_point, primitives: &[u64]| Call::Paint {
+            renderer,
+            pixels_per_point,
+            primitives: ids(primitives),
+        };
+        assert_eq!(
+            target.take_calls(),
+            [
+                Call::SaveState,
+                update(0, &[]),
+                paint(0, 1.0, &[3]),
+                update(0, &[]),
+                paint(0, 2.0, &[5]),
+                update(1, &[10, 11]),
+                paint(1, 1.0, &[1, 4]),
+                update(0, &[]),
+                paint(0, 1.0, &[2]),
+                Call::RestoreState,
+            ]
+        );
+    }
+
+    #[test]
+    fn hidden_overlay_only_updates_textures() {
+        let target = RecordingTarget::default();
+        let mut render_targets = render_targets(&target);
+
+        let batches = batch_render_events(
+            vec![event(RenderTargetHandle(1), 0, 1.0, &[1], &[10])].into_iter(),
+        );
+        unsafe { paint_batches(&mut render_targets, &target, batches, false, None) };
+
+        assert_eq!(
+            target.take_calls(),
+            [
+                Call::SaveState,
+                Call::UpdateTextures {
+                    renderer: 1,
+                    set: ids(&[10]),
+                },
+                Call::Paint {
+                    renderer: 1,
+                    pixels_per_point: 1.0,
+                    primitives: Vec::new(),
+                },
+                Call::RestoreState,
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_is_saved_without_anything_to_paint() {
+        let target = RecordingTarget::default();
+        let mut render_targets = render_targets(&target);
+        unsafe { paint_batches(&mut render_targets, &target, Vec::new(), true, None) };
+        assert_eq!(target.take_calls(), []);
+
+        // Nor without a renderer to paint with.
+        let mut render_targets = RenderTargets::with_renderer::<RecordingRenderer>();
+        let handle = render_targets.create_uninitialized_render_target();
+        let batches = batch_render_events(vec![event(handle, 0, 1.0, &[1], &[])].into_iter());
+        unsafe { paint_batches(&mut render_targets, &target, batches, true, None) };
+        assert_eq!(target.take_calls(), []);
+    }
+
+    /// A texture egui manages, like the font atlas, set to `image` as a whole.
+    fn managed_texture(image: ColorImage) -> TexturesDelta {
+        TexturesDelta {
+            set: vec![(
+                TextureId::Managed(0),
+                ImageDelta::full(image, TextureOptions::default()),
+            )],
+            free: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reinitialized_target_gets_the_managed_textures() {
+        let target = RecordingTarget::default();
+        let mut render_targets = render_targets(&target);
+        let handle = RenderTargetHandle(0);
+
+        // Sent whole once, and then only patched.
+        let mut image = ColorImage::new([2, 1], Color32::BLACK);
+        let full = managed_texture(image.clone());
+        assert!(render_targets.managed_textures_delta(handle, full.clone()) == full);
+        let patch = ImageDelta::partial(
+            [1, 0],
+            ColorImage::new([1, 1], Color32::WHITE),
+            TextureOptions::default(),
+        );
+        let patch = TexturesDelta {
+            set: vec![(TextureId::Managed(0), patch)],
+            free: Vec::new(),
+        };
+        assert!(render_targets.managed_textures_delta(handle, patch.clone()) == patch);
+
+        render_targets.renderers.insert(handle, None);
+        assert_eq!(unsafe { render_targets.setup_renderers(&target) }, [handle]);
+
+        // The new renderer gets the whole patched texture, once.
+        image[(1, 0)] = Color32::WHITE;
+        let resent = render_targets.managed_textures_delta(handle, TexturesDelta::default());
+        assert!(resent == managed_texture(image));
+        let resent = render_targets.managed_textures_delta(handle, TexturesDelta::default());
+        assert!(resent == TexturesDelta::default());
+    }
+
+    /// Paints a frame of primitive 1 to render target 1, with `textures_delta` and the images.
+    fn paint_frame(
+        render_targets: &mut RenderTargets<RecordingTarget>,
+        target: &RecordingTarget,
+        images: &mut OverlayImages,
+        textures_delta: TexturesDelta,
+    ) -> Vec<Call> {
+        let mut event = event(RenderTargetHandle(1), 0, 1.0, &[1], &[]);
+        event.textures_delta = textures_delta;
+        let batches = batch_render_events(vec![event].into_iter());
+        unsafe { paint_batches(render_targets, target, batches, true, Some(images)) };
+        target.take_calls()
+    }
+
+    #[test]
+    fn failed_resize_resends_the_textures() {
+        let mut target = RecordingTarget::default();
+        let mut render_targets = render_targets(&target);
+        let mut images = OverlayImages::default();
+        let image = images.insert(
+            ColorImage::new([1, 1], Color32::WHITE),
+            TextureOptions::default(),
+        );
+        let handle = RenderTargetHandle(1);
+        let font = TextureId::Managed(0);
+
+        let update = |renderer| Call::UpdateTextures {
+            renderer,
+            set: vec![font, image.id()],
+        };
+        let paint = |renderer| Call::Paint {
+            renderer,
+            pixels_per_point: 1.0,
+            primitives: ids(&[1]),
+        };
+
+        let font_texture = managed_texture(ColorImage::new([1, 1], Color32::WHITE));
+        assert_eq!(
+            paint_frame(&mut render_targets, &target, &mut images, font_texture),
+            [Call::SaveState, update(1), paint(1), Call::RestoreState]
+        );
+
+        // The renderer fails to pick the resized buffers back up, and is dropped.
+        target.fail_resize = Some(1);
+        render_targets.release_render_targets();
+        unsafe { render_targets.recreate_render_targets(&target) };
+        target.fail_resize = None;
+        assert!(render_targets.from_handle(handle).is_none());
+        assert_eq!(
+            paint_frame(
+                &mut render_targets,
+                &target,
+                &mut images,
+                TexturesDelta::default()
+            ),
+            [Call::SaveState, Call::RestoreState]
+        );
+
+        // Set up again on the next present, as `setup_render_targets` does.
+        for initialized in unsafe { render_targets.setup_renderers(&target) } {
+            assert_eq!(initialized, handle);
+            images.forget_uploads(initialized);
+        }
+        assert_eq!(target.take_calls(), [Call::Init]);
+        assert_eq!(
+            paint_frame(
+                &mut render_targets,
+                &target,
+                &mut images,
+                TexturesDelta::default()
+            ),
+            [Call::SaveState, update(2), paint(2), Call::RestoreState]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! The backends the overlay paints with.
+//!
+//! [`RenderTargets`](super::RenderTargets) only talks to an [`OverlayRenderer`], so a render target
+//! can be painted by [`DirectX11Renderer`] or by anything else that paints egui primitives to what
+//! the game presents.
+
+use std::any::Any;
+
+use epaint::{textures::TexturesDelta, ClippedPrimitive};
+use epaint_dx11::{DeviceStateGuard, DirectX11Error, DirectX11Renderer};
+use windows::Win32::Graphics::Dxgi::IDXGISwapChain;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("DirectX 11 renderer failed: {0}")]
+    DirectX11(#[from] DirectX11Error),
+}
+
+/// Paints egui primitives to `Target`, the thing the game presents, e.g. its swapchain.
+///
+/// Every method is called on the game thread, with the target the game is presenting or resizing
+/// at that moment. Renderers hold on to what they created from their target, but not the target
+/// itself, which is why it is passed to every call and has to be the one they were created from.
+pub trait OverlayRenderer<Target>: Send + Sync {
+    /// # Safety
+    /// `target` has to be alive for the call.
+    unsafe fn init_from_target(target: &Target) -> Result<Self, RenderError>
+    where
+        Self: Sized;
+
+    /// Saves what the game has set up, which is given back once the returned guard is dropped.
+    ///
+    /// The state belongs to the target and not the renderer, so it is saved once a frame with any
+    /// of its renderers around painting all of them.
+    ///
+    /// # Safety
+    /// `target` has to be the live target the renderer was created from, and outlive the guard.
+    unsafe fn save_state(&self, target: &Target) -> Result<Box<dyn Any>, RenderError>;
+
+    /// Uploads and frees textures, before painting primitives that use them.
+    ///
+    /// # Safety
+    /// `target` has to be the live target the renderer was created from.
+    unsafe fn update_textures(
+        &mut self,
+        target: &Target,
+        textures_delta: TexturesDelta,
+    ) -> Result<(), RenderError>;
+
+    /// Paints primitives in points, scaled by `pixels_per_point` to the pixels of the target.
+    ///
+    /// # Safety
+    /// `target` has to be the live target the renderer was created from, and its state has to be
+    /// saved by [`OverlayRenderer::save_state`] for the game to get it back.
+    unsafe fn paint_primitives(
+        &mut self,
+        target: &Target,
+        pixels_per_point: f32,
+        primitives: &[&ClippedPrimitive],
+    ) -> Result<(), RenderError>;
+
+    /// Lets go of everything referencing the buffers of the target, before they are resized.
+    ///
+    /// Nothing is painted until [`OverlayRenderer::resize_target`] is called.
+    fn release_target(&mut self);
+
+    /// Picks the buffers of the target back up after they were resized.
+    ///
+    /// # Safety
+    /// `target` has to be the live target the renderer was created from.
+    unsafe fn resize_target(&mut self, target: &Target) -> Result<(), RenderError>;
+}
+
+impl OverlayRenderer<IDXGISwapChain> for DirectX11Renderer {
+    unsafe fn init_from_target(target: &IDXGISwapChain) -> Result<Self, RenderError> {
+        Ok(DirectX11Renderer::init_from_swapchain(target)?)
+    }
+
+    unsafe fn save_state(&self, target: &IDXGISwapChain) -> Result<Box<dyn Any>, RenderError> {
+        Ok(Box::new(DeviceStateGuard::save(target)?))
+    }
+
+    unsafe fn update_textures(
+        &mut self,
+        target: &IDXGISwapChain,
+        textures_delta: TexturesDelta,
+    ) -> Result<(), RenderError> {
+        Ok(DirectX11Renderer::update_textures(
+            self,
+            target,
+            textures_delta,
+        )?)
+    }
+
+    unsafe fn paint_primitives(
+        &mut self,
+        target: &IDXGISwapChain,
+        pixels_per_point: f32,
+        primitives: &[&ClippedPrimitive],
+    ) -> Result<(), RenderError> {
+        Ok(self.paint_batch(target, pixels_per_point, primitives)?)
+    }
+
+    fn release_target(&mut self) {
+        self.release_render_target();
+    }
+
+    unsafe fn resize_target(&mut self, target: &IDXGISwapChain) -> Result<(), RenderError> {
+        Ok(self.recreate_render_target(target)?)
+    }
+}
@@ -0,0 +1,317 @@
+//! A dock tab listing the schedules with their systems, sets, ordering and ambiguities.
+//!
+//! A schedule is taken out of [`Schedules`] while it runs, so the graphs are snapshotted from
+//! both [`First`] and [`Present`] and merged, which between them see every schedule.
+
+use std::collections::{BTreeMap, HashMap};
+
+use bevy_app::prelude::*;
+use bevy_ecs::{
+    prelude::*,
+    schedule::{BoxedCondition, ExecutorKind, NodeId, Schedule, Schedules},
+};
+use bevy_utils::{get_short_name, petgraph::Direction};
+
+use crate::{
+    dock::{DockAppExt, DockTab},
+    render::Present,
+};
+
+#[derive(Debug, Clone)]
+pub struct SystemInfo {
+    pub name: String,
+    pub sets: Vec<String>,
+    pub conditions: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SetInfo {
+    pub name: String,
+    pub conditions: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ambiguity {
+    pub a: String,
+    pub b: String,
+    /// The components both access, empty if the conflict is on the whole world.
+    pub components: Vec<String>,
+}
+
+/// What we know about a schedule, as of the last snapshot.
+#[derive(Debug, Clone)]
+pub struct ScheduleInfo {
+    pub executor: String,
+    pub systems: Vec<SystemInfo>,
+    pub sets: Vec<SetInfo>,
+    /// The first node has to run before the second one.
+    pub edges: Vec<(String, String)>,
+    pub ambiguities: Vec<Ambiguity>,
+}
+
+impl ScheduleInfo {
+    fn new(schedule: &Schedule, world: &World) -> Self {
+        let graph = schedule.graph();
+        let short_name = |name: &str| get_short_name(name);
+        let conditions = |conditions: &[BoxedCondition]| {
+            conditions
+                .iter()
+                .map(|condition| short_name(&condition.name()))
+                .collect::<Vec<_>>()
+        };
+
+        let mut names: HashMap<NodeId, String> = HashMap::new();
+        for (id, system, _) in graph.systems() {
+            names.insert(id, short_name(&system.name()));
+        }
+        // Name the sets systems are implicitly in after their system, as that is what users see.
+        let hierarchy = graph.hierarchy().graph();
+        for (id, set, _) in graph.system_sets() {
+            let name = match set.system_type() {
+                Some(_) => hierarchy
+                    .neighbors(id)
+                    .filter_map(|child| names.get(&child).cloned())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                None => format!("{:?}", set),
+            };
+            names.insert(id, name);
+        }
+        let name = |id: &NodeId| {
+            names
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| format!("{:?}", id))
+        };
+
+        let is_named_set = |id: NodeId| {
+            graph
+                .get_set_at(id)
+                .is_some_and(|set| set.system_type().is_none() && !set.is_anonymous())
+        };
+
+        let systems = graph
+            .systems()
+            .map(|(id, _, system_conditions)| SystemInfo {
+                name: name(&id),
+                sets: hierarchy
+                    .neighbors_directed(id, Direction::Incoming)
+                    .filter(|parent| is_named_set(*parent))
+                    .map(|parent| name(&parent))
+                    .collect(),
+                conditions: conditions(system_conditions),
+            })
+            .collect();
+        let sets = graph
+            .system_sets()
+            .filter(|(id, ..)| is_named_set(*id))
+            .map(|(id, _, set_conditions)| SetInfo {
+                name: name(&id),
+                conditions: conditions(set_conditions),
+            })
+            .collect();
+        let edges = graph
+            .dependency()
+            .graph()
+            .all_edges()
+            .map(|(before, after, _)| (name(&before), name(&after)))
+            .collect();
+        let ambiguities = graph
+            .conflicting_systems()
+            .iter()
+            .map(|(a, b, components)| Ambiguity {
+                a: name(a),
+                b: name(b),
+                components: components
+                    .iter()
+                    .filter_map(|id| world.components().get_info(*id))
+                    .map(|info| short_name(info.name()))
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            executor: match schedule.get_executor_kind() {
+                ExecutorKind::SingleThreaded => "single threaded",
+                ExecutorKind::Simple => "simple",
+                ExecutorKind::MultiThreaded => "multi threaded",
+            }
+            .to_owned(),
+            systems,
+            sets,
+            edges,
+            ambiguities,
+        }
+    }
+}
+
+/// The snapshots of the schedules, by label.
+#[derive(Default, Resource)]
+pub struct ScheduleGraphs {
+    schedules: BTreeMap<String, ScheduleInfo>,
+    /// Bumped to ask for a new snapshot.
+    generation: u64,
+}
+
+impl ScheduleGraphs {
+    pub fn refresh(&mut self) {
+        self.generation += 1;
+    }
+}
+
+/// Snapshots the schedules that are not running, whenever a refresh was asked for.
+pub fn snapshot_schedules(world: &mut World, mut seen_generation: Local<u64>) {
+    let generation = world.resource::<ScheduleGraphs>().generation;
+    if *seen_generation == generation {
+        return;
+    }
+    *seen_generation = generation;
+
+    let snapshots: Vec<(String, ScheduleInfo)> = world
+        .resource::<Schedules>()
+        .iter()
+        .map(|(label, schedule)| (format!("{:?}", label), ScheduleInfo::new(schedule, world)))
+        .collect();
+    world
+        .resource_mut::<ScheduleGraphs>()
+        .schedules
+        .extend(snapshots);
+}
+
+#[derive(Default)]
+pub struct ScheduleViewerTab {
+    selected: Option<String>,
+    search: String,
+}
+
+impl ScheduleViewerTab {
+    fn schedule_ui(&self, ui: &mut egui::Ui, info: &ScheduleInfo) {
+        let search = self.search.to_lowercase();
+        let matches = |text: &str| search.is_empty() || text.to_lowercase().contains(&search);
+
+        ui.label(format!("Executor: {}", info.executor));
+
+        egui::CollapsingHeader::new(format!("Systems ({})", info.systems.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                for system in info.systems.iter().filter(|system| matches(&system.name)) {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.monospace(&system.name);
+                        for set in &system.sets {
+                            ui.weak(format!("in {}", set));
+                        }
+                        for condition in &system.conditions {
+                            ui.weak(format!("if {}", condition));
+                        }
+                    });
+                }
+            });
+
+        egui::CollapsingHeader::new(format!("Sets ({})", info.sets.len())).show(ui, |ui| {
+            for set in info.sets.iter().filter(|set| matches(&set.name)) {
+                ui.horizontal_wrapped(|ui| {
+                    ui.monospace(&set.name);
+                    for condition in &set.conditions {
+                        ui.weak(format!("if {}", condition));
+                    }
+                });
+            }
+        });
+
+        egui::CollapsingHeader::new(format!("Ordering ({})", info.edges.len())).show(ui, |ui| {
+            for (before, after) in info
+                .edges
+                .iter()
+                .filter(|(before, after)| matches(before) || matches(after))
+            {
+                ui.monospace(format!("{} -> {}", before, after));
+            }
+        });
+
+        let header = format!("Ambiguities ({})", info.ambiguities.len());
+        let header = match info.ambiguities.is_empty() {
+            true => egui::RichText::new(header),
+            false => egui::RichText::new(header).color(egui::Color32::GOLD),
+        };
+        egui::CollapsingHeader::new(header)
+            .id_source("ambiguities")
+            .show(ui, |ui| {
+                for ambiguity in info
+                    .ambiguities
+                    .iter()
+                    .filter(|ambiguity| matches(&ambiguity.a) || matches(&ambiguity.b))
+                {
+                    let components = match ambiguity.components.is_empty() {
+                        true => "World".to_owned(),
+                        false => ambiguity.components.join(", "),
+                    };
+                    ui.monospace(format!("{} <-> {}", ambiguity.a, ambiguity.b));
+                    ui.weak(format!("    on {}", components));
+                }
+            });
+    }
+}
+
+impl DockTab for ScheduleViewerTab {
+    const ID: &'static str = "schedules";
+
+    fn title(&self) -> egui::WidgetText {
+        "Schedules".into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, world: &mut World) {
+        let mut graphs = world.resource_mut::<ScheduleGraphs>();
+        if graphs.generation == 0 {
+            graphs.refresh();
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Refresh").clicked() {
+                graphs.refresh();
+            }
+            ui.add(
+                egui::TextEdit::singleline(&mut self.search)
+                    .hint_text("search")
+                    .desired_width(160.0),
+            );
+        });
+        ui.separator();
+
+        egui::SidePanel::left("schedule_list")
+            .resizable(true)
+            .default_width(160.0)
+            .show_inside(ui, |ui| {
+                for label in graphs.schedules.keys() {
+                    let selected = self.selected.as_ref() == Some(label);
+                    if ui.selectable_label(selected, label).clicked() {
+                        self.selected = Some(label.clone());
+                    }
+                }
+            });
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            let info = self
+                .selected
+                .as_ref()
+                .and_then(|label| graphs.schedules.get(label));
+            match info {
+                Some(info) => {
+                    egui::ScrollArea::vertical().show(ui, |ui| self.schedule_ui(ui, info));
+                }
+                None => {
+                    ui.weak("Select a schedule");
+                }
+            }
+        });
+    }
+}
+
+pub struct ScheduleViewerPlugin;
+
+impl Plugin for ScheduleViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScheduleGraphs>()
+            .add_dock_tab::<ScheduleViewerTab>()
+            .add_systems(First, snapshot_schedules)
+            .add_systems(Present, snapshot_schedules);
+    }
+}
@@ -1,23 +1,44 @@
+use std::{sync::Arc, time::Instant};
+
 use bevy_app::prelude::*;
 use bevy_ecs::{prelude::*, system::SystemParam};
-use bevy_input::{
-    keyboard::{KeyCode, KeyboardInput},
-    mouse::{MouseButton, MouseButtonInput, MouseMotion, MouseScrollUnit, MouseWheel},
+use bevy_input::gamepad::{GamepadButtonChangedEvent, GamepadSettings};
+use bevy_win32::{clipboard::Clipboard, Win32InputEvent};
+use bevy_window::{PrimaryWindow, Window};
+
+use crate::{
+    paint::PaintLayer,
+    render::{Present, RenderEvent, RenderTargetHandle, RenderTargets},
 };
-use egui::RawInput;
-use epaint::Shadow;
 
-use crate::render::{Present, RenderEvent, RenderTargetHandle, RenderTargets};
+use self::{
+    fonts::Fonts,
+    input::{UiInputEvents, UiInputTranslator, UiWindowState},
+    output::{UiLastOutput, UiOutputEventWriters, UiRepaint},
+    persistence::UiPersistence,
+    theme::{Theme, UiTheme},
+};
 
-// TODO: Should we make UiContext a resource again? then we can just attach the mouse pos directly to it...
+pub mod fonts;
+pub mod input;
+pub mod output;
+pub mod persistence;
+pub mod theme;
 
-// TODO: Attach this to a window.
-// TODO: Add a "primary" ui context.
-#[derive(Clone, Resource)]
+/// An egui context attached to a window entity, rendering to its own render target.
+///
+/// Every window gets one, see [`PrimaryUi`] for getting at the one of the [`PrimaryWindow`].
+#[derive(Component)]
 pub struct UiContext {
     render_target_handle: RenderTargetHandle,
     ctx: egui::Context,
-    mouse_pos: egui::Pos2,
+    input: UiInputTranslator,
+    start_time: Instant,
+    /// The window state the last frame was built against.
+    window: Option<UiWindowState>,
+    last_output: UiLastOutput,
+    /// The primitives of the last frame, presented again while the ui is not repainting.
+    last_primitives: Arc<Vec<epaint::ClippedPrimitive>>,
 }
 
 impl UiContext {
@@ -27,15 +48,33 @@ impl UiContext {
         Self {
             render_target_handle,
             ctx: ui_ctx,
-            mouse_pos: Default::default(),
+            input: Default::default(),
+            start_time: Instant::now(),
+            window: None,
+            last_output: Default::default(),
+            last_primitives: Default::default(),
         }
     }
 
+    /// Sets up a new context with the default [`Theme`], until [`theme::apply_theme`] applies the active one.
     pub fn build_ctx(ctx: &mut egui::Context) {
-        // TODO: Change styling etc...
-        ctx.style_mut(|style| {
-            style.visuals.window_shadow = Shadow::NONE;
-        });
+        ctx.style_mut(|style| Theme::default().apply(style));
+    }
+
+    /// Restores the parts of `memory` egui persists, e.g. the data of widgets and where windows are.
+    ///
+    /// The style is not persisted, so the one the context was set up with is kept.
+    pub fn restore_memory(&mut self, memory: egui::Memory) {
+        let style = self.ctx.style();
+        self.ctx.memory_mut(|current| *current = memory);
+        self.ctx.set_style(style);
+    }
+
+    /// The underlying egui context, for reading its state.
+    ///
+    /// Use [`UiContext::get_mut`] for building the ui.
+    pub fn ctx(&self) -> &egui::Context {
+        &self.ctx
     }
 
     /// Borrows the underlying Egui context mutably.
@@ -60,93 +99,150 @@ impl FromWorld for UiContext {
     }
 }
 
-// TODO: Subscribe to all bevy input.
-
-// TODO: Lock input to UI... maybe reguister this with some function that will prevent messages from going to game.
+/// Marker [`Component`] for the [`UiContext`] of the [`PrimaryWindow`].
+#[derive(Default, Debug, Component, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub struct PrimaryUiContext;
 
+/// Query helper for the common case of drawing to the [`PrimaryUiContext`].
 #[derive(SystemParam)]
-pub struct UiEvents<'w, 's> {
-    kb_evr: EventReader<'w, 's, KeyboardInput>,
-    mm_evr: EventReader<'w, 's, MouseMotion>,
-    mb_evr: EventReader<'w, 's, MouseButtonInput>,
-    mw_evr: EventReader<'w, 's, MouseWheel>,
+pub struct PrimaryUi<'w, 's> {
+    query: Query<'w, 's, &'static mut UiContext, With<PrimaryUiContext>>,
 }
 
-pub fn ui_begin_frame(mut ui_ctx: ResMut<UiContext>, mut ui_events: UiEvents) {
-    let mut raw_input = RawInput::default();
-
-    // TODO: Modifiers
-    // TODO: Screen size
-    // TODO: PPI
+impl PrimaryUi<'_, '_> {
+    /// The egui context of the primary window, `None` until the primary window has a [`UiContext`].
+    pub fn ctx_mut(&mut self) -> Option<&mut egui::Context> {
+        let ui_ctx = self.query.get_single_mut().ok()?;
+        Some(ui_ctx.into_inner().get_mut())
+    }
+}
 
-    for mm_ev in ui_events.mm_evr.iter() {
-        // TODO: Right now we can assume that delta is actually pos, but once we fix that we need to store the mouse position as a component.
-        ui_ctx.mouse_pos = epaint::Pos2 {
-            x: mm_ev.delta.x,
-            y: mm_ev.delta.y,
-        };
+/// Windows that do not have a [`UiContext`] yet.
+type WithoutUiContext = (With<Window>, Without<UiContext>);
 
-        raw_input
-            .events
-            .push(egui::Event::PointerMoved(ui_ctx.mouse_pos));
+/// Attaches a [`UiContext`] to every window that does not have one yet.
+///
+/// The primary one gets the egui memory saved by the last injection.
+pub fn add_ui_contexts(
+    mut commands: Commands,
+    mut render_targets: ResMut<RenderTargets>,
+    mut persistence: ResMut<UiPersistence>,
+    windows: Query<(Entity, Option<&PrimaryWindow>), WithoutUiContext>,
+) {
+    for (window, primary_window) in windows.iter() {
+        let mut ui_ctx = UiContext::new(render_targets.create_uninitialized_render_target());
+        let mut entity = commands.entity(window);
+        if primary_window.is_some() {
+            if let Some(memory) = persistence.take_memory() {
+                ui_ctx.restore_memory(memory);
+            }
+            entity.insert(PrimaryUiContext);
+        }
+        entity.insert(ui_ctx);
     }
+}
 
-    for mb_ev in ui_events.mb_evr.iter() {
-        if let Some(button) = ui_pointer_button_from_mouse_button(mb_ev.button) {
-            raw_input.events.push(egui::Event::PointerButton {
-                pos: ui_ctx.mouse_pos,
-                button: button,
-                pressed: mb_ev.state.is_pressed(),
-                modifiers: Default::default(),
-            });
+#[derive(SystemParam)]
+pub struct UiEvents<'w, 's> {
+    input_evr: EventReader<'w, 's, Win32InputEvent>,
+    gb_evr: EventReader<'w, 's, GamepadButtonChangedEvent>,
+    gamepad_settings: Res<'w, GamepadSettings>,
+}
+
+impl UiEvents<'_, '_> {
+    /// Reads all of the pending input events.
+    pub fn drain(&mut self) -> UiInputEvents {
+        UiInputEvents {
+            window: self.input_evr.iter().cloned().collect(),
+            gamepad_button: self.gb_evr.iter().cloned().collect(),
         }
     }
+}
 
-    for mw_ev in ui_events.mw_evr.iter() {
-        raw_input.events.push(egui::Event::MouseWheel {
-            unit: match mw_ev.unit {
-                MouseScrollUnit::Line => egui::MouseWheelUnit::Line,
-                MouseScrollUnit::Pixel => egui::MouseWheelUnit::Point,
-            },
-            delta: (mw_ev.x, mw_ev.y).into(),
-            modifiers: Default::default(),
-        });
-
-        // TODO: Handle fucking horizontal.
+/// Routes the input to the [`UiContext`] of the window that received it, then begins a frame on
+/// all of them.
+///
+/// Either every context repaints or none does, so that systems in [`UiUpdate`] always draw into a frame.
+pub fn ui_begin_frame(
+    mut ui_contexts: Query<(Entity, &Window, &mut UiContext, Option<&PrimaryUiContext>)>,
+    mut ui_events: UiEvents,
+    mut clipboard: ResMut<Clipboard>,
+    mut repaint: ResMut<UiRepaint>,
+) {
+    let events = ui_events.drain();
+    // Input that isn't tied to a window goes to the focused one, or the primary one if none is.
+    let unrouted_window = ui_contexts
+        .iter()
+        .find(|(_, window, ..)| window.focused)
+        .or_else(|| ui_contexts.iter().find(|(.., primary)| primary.is_some()))
+        .map(|(entity, ..)| entity);
 
-        raw_input
-            .events
-            .push(egui::Event::Scroll((mw_ev.x, mw_ev.y).into()));
+    let mut repainting = repaint.is_due();
+    let mut raw_inputs = Vec::new();
+    for (entity, window, mut ui_ctx, _) in ui_contexts.iter_mut() {
+        let window = UiWindowState::from(window);
+        let events = events.for_window(entity, unrouted_window == Some(entity));
+        let time = ui_ctx.start_time.elapsed().as_secs_f64();
+        let mut raw_input =
+            ui_ctx
+                .input
+                .translate(Some(window), time, &events, &ui_events.gamepad_settings);
+        input::fill_paste_events(&mut raw_input.events, || clipboard.get_text());
+        let window_changed = ui_ctx.window.replace(window) != Some(window);
+        repainting |= window_changed || !raw_input.events.is_empty();
+        raw_inputs.push((entity, raw_input));
     }
 
-    for kb_ev in ui_events.kb_evr.iter() {
-        if let Some(key_code) = kb_ev.key_code {
-            // TODO: Check if modifier, if so add it to the modifiers
-
-            if let Some(key) = ui_key_from_key_code(key_code) {
-                raw_input.events.push(egui::Event::Key {
-                    key,
-                    pressed: kb_ev.state.is_pressed(),
-                    repeat: false,
-                    modifiers: Default::default(),
-                });
-            }
+    repaint.set_repainting(repainting);
+    if !repainting {
+        return;
+    }
+    for (entity, raw_input) in raw_inputs {
+        if let Ok((_, _, mut ui_ctx, _)) = ui_contexts.get_mut(entity) {
+            ui_ctx.get_mut().begin_frame(raw_input);
         }
     }
-
-    ui_ctx.get_mut().begin_frame(raw_input.clone())
 }
 
-pub fn ui_end_frame(mut ev_render: EventWriter<RenderEvent>, mut ui_ctx: ResMut<UiContext>) {
-    let ctx = ui_ctx.get_mut();
-    let out = ctx.end_frame();
-    // Use the egui contexts own tesselation, so that the ui's registered fonts exist.
-    let primitives = ctx.tessellate(out.shapes);
-    ev_render.send(RenderEvent {
-        render_target_handle: ui_ctx.render_target_handle,
-        primitives,
-        textures_delta: out.textures_delta,
-    });
+pub fn ui_end_frame(
+    mut ev_render: EventWriter<RenderEvent>,
+    mut ui_contexts: Query<(Entity, &mut UiContext, Option<&PaintLayer>)>,
+    repaint: Res<UiRepaint>,
+    mut output_writers: UiOutputEventWriters,
+) {
+    for (window, mut ui_ctx, layer) in ui_contexts.iter_mut() {
+        let ui_ctx = ui_ctx.as_mut();
+        let layer = layer.copied().unwrap_or(PaintLayer::UI);
+        if !repaint.is_repainting() {
+            ev_render.send(RenderEvent {
+                render_target_handle: ui_ctx.render_target_handle,
+                layer,
+                pixels_per_point: ui_ctx.ctx.pixels_per_point(),
+                primitives: ui_ctx.last_primitives.clone(),
+                textures_delta: Default::default(),
+            });
+            continue;
+        }
+
+        let out = ui_ctx.ctx.end_frame();
+        output::send_platform_output(
+            window,
+            out.platform_output,
+            out.repaint_after,
+            &mut ui_ctx.last_output,
+            &mut output_writers,
+        );
+        // Use the egui contexts own tesselation, so that the ui's registered fonts exist.
+        let primitives = Arc::new(ui_ctx.ctx.tessellate(out.shapes));
+        ui_ctx.last_primitives = primitives.clone();
+        ev_render.send(RenderEvent {
+            render_target_handle: ui_ctx.render_target_handle,
+            layer,
+            pixels_per_point: ui_ctx.ctx.pixels_per_point(),
+            primitives,
+            textures_delta: out.textures_delta,
+        });
+    }
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
@@ -156,113 +252,80 @@ pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<UiContext>()
+        app.init_resource::<UiRepaint>()
+            .init_resource::<UiPersistence>()
+            .init_resource::<UiTheme>()
+            .init_resource::<Fonts>()
+            .init_resource::<output::PendingOpenUrl>()
+            .add_event::<output::UiCursorIconChanged>()
+            .add_event::<output::UiCopiedText>()
+            .add_event::<output::UiOpenUrl>()
+            .add_event::<output::UiImeCursor>()
+            .add_event::<output::UiRepaintAfter>()
+            .add_systems(
+                Update,
+                (
+                    add_ui_contexts,
+                    theme::reload_theme,
+                    theme::apply_theme
+                        .after(add_ui_contexts)
+                        .after(theme::reload_theme),
+                    fonts::apply_fonts.after(add_ui_contexts),
+                ),
+            )
+            .add_systems(
+                Last,
+                persistence::save_ui_state.run_if(persistence::ui_state_save_due),
+            )
+            .add_systems(
+                Present,
+                (
+                    ui_begin_frame,
+                    ui_end_frame.after(ui_begin_frame),
+                    output::ui_confirm_open_url.in_set(UiUpdate),
+                ),
+            )
             .add_systems(
                 Present,
-                (ui_begin_frame, ui_end_frame.after(ui_begin_frame)),
+                (
+                    output::apply_cursor_icon,
+                    output::copy_to_clipboard,
+                    output::queue_open_url,
+                    output::apply_ime_cursor,
+                    output::schedule_repaint,
+                )
+                    .after(ui_end_frame),
             )
-            .configure_set(Present, UiUpdate.after(ui_begin_frame).before(ui_end_frame));
+            .configure_set(
+                Present,
+                UiUpdate
+                    .after(ui_begin_frame)
+                    .before(ui_end_frame)
+                    .run_if(output::ui_repainting),
+            );
     }
 }
 
-pub fn ui_pointer_button_from_mouse_button(
-    mouse_button: MouseButton,
-) -> Option<egui::PointerButton> {
-    match mouse_button {
-        bevy_input::mouse::MouseButton::Left => Some(egui::PointerButton::Primary),
-        bevy_input::mouse::MouseButton::Right => Some(egui::PointerButton::Secondary),
-        bevy_input::mouse::MouseButton::Middle => Some(egui::PointerButton::Middle),
-        bevy_input::mouse::MouseButton::Other(id) => todo!("support extra"),
-    }
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-pub fn ui_key_from_key_code(keycode: KeyCode) -> Option<egui::Key> {
-    match keycode {
-        KeyCode::Key1 => Some(egui::Key::Num1),
-        KeyCode::Key2 => Some(egui::Key::Num2),
-        KeyCode::Key3 => Some(egui::Key::Num3),
-        KeyCode::Key4 => Some(egui::Key::Num4),
-        KeyCode::Key5 => Some(egui::Key::Num5),
-        KeyCode::Key6 => Some(egui::Key::Num6),
-        KeyCode::Key7 => Some(egui::Key::Num7),
-        KeyCode::Key8 => Some(egui::Key::Num8),
-        KeyCode::Key9 => Some(egui::Key::Num9),
-        KeyCode::Key0 => Some(egui::Key::Num0),
-        KeyCode::A => Some(egui::Key::A),
-        KeyCode::B => Some(egui::Key::B),
-        KeyCode::C => Some(egui::Key::C),
-        KeyCode::D => Some(egui::Key::D),
-        KeyCode::E => Some(egui::Key::E),
-        KeyCode::F => Some(egui::Key::F),
-        KeyCode::G => Some(egui::Key::G),
-        KeyCode::H => Some(egui::Key::H),
-        KeyCode::I => Some(egui::Key::I),
-        KeyCode::J => Some(egui::Key::J),
-        KeyCode::K => Some(egui::Key::K),
-        KeyCode::L => Some(egui::Key::L),
-        KeyCode::M => Some(egui::Key::M),
-        KeyCode::N => Some(egui::Key::N),
-        KeyCode::O => Some(egui::Key::O),
-        KeyCode::P => Some(egui::Key::P),
-        KeyCode::Q => Some(egui::Key::Q),
-        KeyCode::R => Some(egui::Key::R),
-        KeyCode::S => Some(egui::Key::S),
-        KeyCode::T => Some(egui::Key::T),
-        KeyCode::U => Some(egui::Key::U),
-        KeyCode::V => Some(egui::Key::V),
-        KeyCode::W => Some(egui::Key::W),
-        KeyCode::X => Some(egui::Key::X),
-        KeyCode::Y => Some(egui::Key::Y),
-        KeyCode::Z => Some(egui::Key::Z),
-        KeyCode::Escape => Some(egui::Key::Escape),
-        KeyCode::F1 => Some(egui::Key::F1),
-        KeyCode::F2 => Some(egui::Key::F2),
-        KeyCode::F3 => Some(egui::Key::F3),
-        KeyCode::F4 => Some(egui::Key::F4),
-        KeyCode::F5 => Some(egui::Key::F5),
-        KeyCode::F6 => Some(egui::Key::F6),
-        KeyCode::F7 => Some(egui::Key::F7),
-        KeyCode::F8 => Some(egui::Key::F8),
-        KeyCode::F9 => Some(egui::Key::F9),
-        KeyCode::F10 => Some(egui::Key::F10),
-        KeyCode::F11 => Some(egui::Key::F11),
-        KeyCode::F12 => Some(egui::Key::F12),
-        KeyCode::F13 => Some(egui::Key::F13),
-        KeyCode::F14 => Some(egui::Key::F14),
-        KeyCode::F15 => Some(egui::Key::F15),
-        KeyCode::F16 => Some(egui::Key::F16),
-        KeyCode::F17 => Some(egui::Key::F17),
-        KeyCode::F18 => Some(egui::Key::F18),
-        KeyCode::F19 => Some(egui::Key::F19),
-        KeyCode::F20 => Some(egui::Key::F20),
-        KeyCode::Insert => Some(egui::Key::Insert),
-        KeyCode::Home => Some(egui::Key::Home),
-        KeyCode::Delete => Some(egui::Key::Delete),
-        KeyCode::End => Some(egui::Key::End),
-        KeyCode::PageDown => Some(egui::Key::PageDown),
-        KeyCode::PageUp => Some(egui::Key::PageUp),
-        KeyCode::Left => Some(egui::Key::ArrowLeft),
-        KeyCode::Up => Some(egui::Key::ArrowUp),
-        KeyCode::Right => Some(egui::Key::ArrowRight),
-        KeyCode::Down => Some(egui::Key::ArrowDown),
-        KeyCode::Back => Some(egui::Key::Backspace),
-        KeyCode::Return => Some(egui::Key::Enter),
-        KeyCode::Space => Some(egui::Key::Space),
-        KeyCode::Compose => None,
-        KeyCode::Caret => None,
-        KeyCode::Numlock => None,
-        KeyCode::Numpad0 => Some(egui::Key::Num0),
-        KeyCode::Numpad1 => Some(egui::Key::Num1),
-        KeyCode::Numpad2 => Some(egui::Key::Num2),
-        KeyCode::Numpad3 => Some(egui::Key::Num3),
-        KeyCode::Numpad4 => Some(egui::Key::Num4),
-        KeyCode::Numpad5 => Some(egui::Key::Num5),
-        KeyCode::Numpad6 => Some(egui::Key::Num6),
-        KeyCode::Numpad7 => Some(egui::Key::Num7),
-        KeyCode::Numpad8 => Some(egui::Key::Num8),
-        KeyCode::Numpad9 => Some(egui::Key::Num9),
-        KeyCode::Equals => Some(egui::Key::PlusEquals),
-        KeyCode::Tab => Some(egui::Key::Tab),
-        _ => None,
+    #[test]
+    fn restoring_memory_keeps_the_style() {
+        let handle = RenderTargets::default().create_uninitialized_render_target();
+        let mut ui_ctx = UiContext::new(handle);
+        let style = ui_ctx.ctx().style();
+
+        let id = egui::Id::new("panel");
+        let mut memory = egui::Memory::default();
+        memory.data.insert_persisted(id, 42u32);
+        memory.options.screen_reader = true;
+        ui_ctx.restore_memory(memory);
+
+        assert_eq!(ui_ctx.ctx().style(), style);
+        ui_ctx.ctx().memory_mut(|memory| {
+            assert_eq!(memory.data.get_persisted::<u32>(id), Some(42));
+            assert!(memory.options.screen_reader);
+        });
     }
 }
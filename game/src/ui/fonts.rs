@@ -0,0 +1,260 @@
+//! Fonts shared by every ui and painter context, so text looks the same wherever it is drawn.
+//!
+//! Every `.ttf` or `.otf` file in [`FONTS_DIR`] in the config directory is loaded as a family named
+//! after the file, which themes can use through [`ThemeFontFamily::Named`]. Icon fonts go in its
+//! [`ICON_FONTS_DIR`] subdirectory and are a fallback of every family.
+//!
+//! [`ThemeFontFamily::Named`]: super::theme::ThemeFontFamily::Named
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy_ecs::prelude::*;
+use egui::{FontData, FontDefinitions, FontFamily};
+
+use crate::{paint::PainterContext, utils};
+
+use super::{output::UiRepaint, UiContext};
+
+pub const FONTS_DIR: &str = "fonts";
+pub const ICON_FONTS_DIR: &str = "icons";
+
+#[derive(Debug, thiserror::Error)]
+pub enum FontError {
+    #[error("Failed to read font: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Not a ttf or otf font")]
+    Invalid,
+}
+
+/// The fonts of all ui and painter contexts, on top of egui's default ones.
+///
+/// Changing it rebuilds the [`FontDefinitions`] and hands them to every context.
+#[derive(Resource)]
+pub struct Fonts {
+    fonts: BTreeMap<String, FontData>,
+    /// The fonts of a family, in order of preference, before the default fonts of the family.
+    families: BTreeMap<FontFamily, Vec<String>>,
+    /// Fonts that every family falls back to, for glyphs none of its own fonts have.
+    fallbacks: Vec<String>,
+    definitions: FontDefinitions,
+}
+
+impl Fonts {
+    /// Only egui's default fonts, without anything from the config directory.
+    pub fn builtin() -> Self {
+        Self {
+            fonts: BTreeMap::new(),
+            families: BTreeMap::new(),
+            fallbacks: Vec::new(),
+            definitions: FontDefinitions::default(),
+        }
+    }
+
+    /// Adds a font by name, replacing any font of the same name.
+    ///
+    /// The font is not used until it is part of a family, see [`Fonts::push_to_family`].
+    pub fn insert_font(
+        &mut self,
+        name: impl Into<String>,
+        data: FontData,
+    ) -> Result<(), FontError> {
+        // egui panics on fonts it can not parse, so they never get that far.
+        ab_glyph::FontRef::try_from_slice_and_index(&data.font, data.index)
+            .map_err(|_| FontError::Invalid)?;
+        self.fonts.insert(name.into(), data);
+        self.rebuild();
+        Ok(())
+    }
+
+    /// Adds a font from the contents of a `.ttf` or `.otf` file.
+    pub fn insert_font_bytes(
+        &mut self,
+        name: impl Into<String>,
+        bytes: impl Into<Cow<'static, [u8]>>,
+    ) -> Result<(), FontError> {
+        self.insert_font(
+            name,
+            FontData {
+                font: bytes.into(),
+                index: 0,
+                tweak: Default::default(),
+            },
+        )
+    }
+
+    /// Reads a `.ttf` or `.otf` file and adds it as a font.
+    pub fn load_font(&mut self, name: impl Into<String>, path: &Path) -> Result<(), FontError> {
+        self.insert_font_bytes(name, fs::read(path)?)
+    }
+
+    /// Uses the font for the family, after the fonts already in it.
+    ///
+    /// Does nothing if the font is already in the family.
+    ///
+    /// For [`FontFamily::Proportional`] and [`FontFamily::Monospace`] the added fonts come before
+    /// egui's default fonts, named families fall back to the proportional fonts.
+    pub fn push_to_family(&mut self, family: FontFamily, name: impl Into<String>) {
+        let name = name.into();
+        let names = self.families.entry(family).or_default();
+        if !names.contains(&name) {
+            names.push(name);
+            self.rebuild();
+        }
+    }
+
+    /// Adds a font as a fallback of every family, e.g. an icon font.
+    pub fn insert_icon_font(
+        &mut self,
+        name: impl Into<String>,
+        bytes: impl Into<Cow<'static, [u8]>>,
+    ) -> Result<(), FontError> {
+        let name = name.into();
+        self.insert_font_bytes(name.clone(), bytes)?;
+        if !self.fallbacks.contains(&name) {
+            self.fallbacks.push(name);
+            self.rebuild();
+        }
+        Ok(())
+    }
+
+    /// Whether text can be laid out in the family, egui panics on families it does not know.
+    pub fn has_family(&self, family: &FontFamily) -> bool {
+        self.definitions.families.contains_key(family)
+    }
+
+    /// What every context is given.
+    pub fn definitions(&self) -> &FontDefinitions {
+        &self.definitions
+    }
+
+    fn rebuild(&mut self) {
+        let mut definitions = FontDefinitions::default();
+        let default_proportional = definitions
+            .families
+            .get(&FontFamily::Proportional)
+            .cloned()
+            .unwrap_or_default();
+
+        for (name, data) in &self.fonts {
+            definitions.font_data.insert(name.clone(), data.clone());
+        }
+        for (family, names) in &self.families {
+            let names = names
+                .iter()
+                .filter(|name| self.fonts.contains_key(*name))
+                .cloned();
+            let defaults = match family {
+                FontFamily::Name(_) => default_proportional.clone(),
+                _ => definitions.families.remove(family).unwrap_or_default(),
+            };
+            definitions
+                .families
+                .insert(family.clone(), names.chain(defaults).collect());
+        }
+        for fonts in definitions.families.values_mut() {
+            fonts.extend(
+                self.fallbacks
+                    .iter()
+                    .filter(|name| self.fonts.contains_key(*name))
+                    .cloned(),
+            );
+        }
+        self.definitions = definitions;
+    }
+
+    /// Loads the fonts in `dir`, each as a family named after its file, and the icon fonts in
+    /// the [`ICON_FONTS_DIR`] inside of it.
+    ///
+    /// Fonts that fail to load are logged and skipped.
+    pub fn load_dir(&mut self, dir: &Path) {
+        for path in font_files(dir) {
+            let Some(name) = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            match self.load_font(name.clone(), &path) {
+                Ok(()) => {
+                    log::info!("loaded font {}", path.display());
+                    self.push_to_family(FontFamily::Name(name.as_str().into()), name);
+                }
+                Err(err) => log::warn!("failed to load font {}: {}", path.display(), err),
+            }
+        }
+        for path in font_files(&dir.join(ICON_FONTS_DIR)) {
+            let Some(name) = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            let loaded = fs::read(&path)
+                .map_err(FontError::from)
+                .and_then(|bytes| self.insert_icon_font(name, bytes));
+            match loaded {
+                Ok(()) => log::info!("loaded icon font {}", path.display()),
+                Err(err) => log::warn!("failed to load icon font {}: {}", path.display(), err),
+            }
+        }
+    }
+}
+
+/// The `.ttf` and `.otf` files in `dir` sorted by name, empty if it does not exist.
+fn font_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| {
+                ext.eq_ignore_ascii_case("ttf") || ext.eq_ignore_ascii_case("otf")
+            })
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+impl FromWorld for Fonts {
+    fn from_world(_world: &mut World) -> Self {
+        let mut fonts = Self::builtin();
+        if let Some(dir) = utils::config_dir() {
+            fonts.load_dir(&dir.join(FONTS_DIR));
+        }
+        fonts
+    }
+}
+
+/// Gives the fonts to new ui and painter contexts, and to all of them when they change.
+pub fn apply_fonts(
+    fonts: Res<Fonts>,
+    mut repaint: ResMut<UiRepaint>,
+    ui_contexts: Query<Ref<UiContext>>,
+    painter_contexts: Query<Ref<PainterContext>>,
+) {
+    let ctxs = ui_contexts
+        .iter()
+        .filter(|ui_ctx| fonts.is_changed() || ui_ctx.is_added())
+        .map(|ui_ctx| ui_ctx.ctx().clone())
+        .chain(
+            painter_contexts
+                .iter()
+                .filter(|painter_ctx| fonts.is_changed() || painter_ctx.is_added())
+                .map(|painter_ctx| painter_ctx.ctx().clone()),
+        );
+    for ctx in ctxs {
+        // Takes effect on the next frame of the context.
+        ctx.set_fonts(fonts.definitions().clone());
+    }
+    if fonts.is_changed() {
+        repaint.request();
+    }
+}
@@ -0,0 +1,683 @@
+use bevy_ecs::entity::Entity;
+use bevy_input::{
+    gamepad::{GamepadButton, GamepadButtonChangedEvent, GamepadButtonType, GamepadSettings},
+    keyboard::KeyCode,
+    mouse::{MouseButton, MouseScrollUnit},
+};
+use bevy_win32::Win32InputEvent;
+use bevy_window::Window;
+use egui::RawInput;
+
+/// How many points a single line of scrolling moves.
+const POINTS_PER_SCROLL_LINE: f32 = 50.0;
+
+/// The state of the window that a [`RawInput`] is built against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiWindowState {
+    /// Size of the window in points.
+    pub size: egui::Vec2,
+    pub pixels_per_point: f32,
+    pub focused: bool,
+}
+
+impl From<&Window> for UiWindowState {
+    fn from(window: &Window) -> Self {
+        Self {
+            size: egui::vec2(window.width(), window.height()),
+            pixels_per_point: window.scale_factor() as f32,
+            focused: window.focused,
+        }
+    }
+}
+
+/// The input events received since the last ui frame.
+#[derive(Debug, Default, Clone)]
+pub struct UiInputEvents {
+    /// The window input, in the order it arrived.
+    pub window: Vec<Win32InputEvent>,
+    pub gamepad_button: Vec<GamepadButtonChangedEvent>,
+}
+
+impl UiInputEvents {
+    /// Picks out the events that were received by `window`.
+    ///
+    /// Events that are not tied to a window, like gamepad input, are only kept when `unrouted` is set.
+    pub fn for_window(&self, window: Entity, unrouted: bool) -> UiInputEvents {
+        UiInputEvents {
+            window: self
+                .window
+                .iter()
+                .filter(|ev| ev.window() == window)
+                .cloned()
+                .collect(),
+            gamepad_button: match unrouted {
+                true => self.gamepad_button.clone(),
+                false => Vec::new(),
+            },
+        }
+    }
+}
+
+/// Translates bevy input events and window state into an egui [`RawInput`].
+///
+/// Holds on to what egui expects us to remember between frames, such as the pointer position and held modifiers.
+#[derive(Debug, Default, Clone)]
+pub struct UiInputTranslator {
+    pointer_pos: egui::Pos2,
+    modifiers: egui::Modifiers,
+    last_time: Option<f64>,
+}
+
+impl UiInputTranslator {
+    /// Builds the [`RawInput`] for a frame starting at `time` seconds.
+    ///
+    /// Without a `window` egui falls back to its own defaults for the screen and scale. Pastes are
+    /// left empty for [`fill_paste_events`], so that translating never touches the clipboard.
+    pub fn translate(
+        &mut self,
+        window: Option<UiWindowState>,
+        time: f64,
+        events: &UiInputEvents,
+        gamepad_settings: &GamepadSettings,
+    ) -> RawInput {
+        let pixels_per_point = window.map_or(1.0, |w| w.pixels_per_point);
+        let predicted_dt = match self.last_time {
+            Some(last_time) if time > last_time => (time - last_time) as f32,
+            _ => RawInput::default().predicted_dt,
+        };
+        self.last_time = Some(time);
+
+        let mut raw_input = RawInput {
+            screen_rect: window.map(|w| egui::Rect::from_min_size(egui::Pos2::ZERO, w.size)),
+            pixels_per_point: window.map(|w| w.pixels_per_point),
+            time: Some(time),
+            predicted_dt,
+            focused: window.is_none_or(|w| w.focused),
+            ..Default::default()
+        };
+
+        for event in events.window.iter() {
+            match event {
+                Win32InputEvent::Keyboard(kb_ev) => {
+                    let Some(key_code) = kb_ev.key_code else {
+                        continue;
+                    };
+                    let pressed = kb_ev.state.is_pressed();
+                    match key_code {
+                        KeyCode::ControlLeft | KeyCode::ControlRight => {
+                            self.modifiers.ctrl = pressed;
+                            self.modifiers.command = pressed;
+                        }
+                        KeyCode::ShiftLeft | KeyCode::ShiftRight => self.modifiers.shift = pressed,
+                        KeyCode::AltLeft | KeyCode::AltRight => self.modifiers.alt = pressed,
+                        _ => (),
+                    }
+
+                    if pressed {
+                        if let Some(ev) = ui_clipboard_event(key_code, self.modifiers) {
+                            raw_input.events.push(ev);
+                        }
+                    }
+
+                    if let Some(key) = ui_key_from_key_code(key_code) {
+                        raw_input.events.push(egui::Event::Key {
+                            key,
+                            pressed,
+                            // egui works this out itself.
+                            repeat: false,
+                            modifiers: self.modifiers,
+                        });
+                    }
+                }
+                Win32InputEvent::Character(chr_ev) => {
+                    // Shortcuts like ctrl+c should not also type the character.
+                    let shortcut = self.modifiers.ctrl || self.modifiers.command;
+                    if !shortcut && !chr_ev.char.is_control() {
+                        raw_input
+                            .events
+                            .push(egui::Event::Text(chr_ev.char.to_string()));
+                    }
+                }
+                Win32InputEvent::CursorMoved(cm_ev) => {
+                    // Logical pixels are points, since we use the window scale factor as `pixels_per_point`.
+                    self.pointer_pos = cm_ev.position.to_array().into();
+                    raw_input
+                        .events
+                        .push(egui::Event::PointerMoved(self.pointer_pos));
+                }
+                Win32InputEvent::MouseButton(mb_ev) => {
+                    if let Some(button) = ui_pointer_button_from_mouse_button(mb_ev.button) {
+                        raw_input.events.push(egui::Event::PointerButton {
+                            pos: self.pointer_pos,
+                            button,
+                            pressed: mb_ev.state.is_pressed(),
+                            modifiers: self.modifiers,
+                        });
+                    }
+                }
+                Win32InputEvent::MouseWheel(mw_ev) => {
+                    let delta = match mw_ev.unit {
+                        MouseScrollUnit::Line => {
+                            egui::vec2(mw_ev.x, mw_ev.y) * POINTS_PER_SCROLL_LINE
+                        }
+                        MouseScrollUnit::Pixel => egui::vec2(mw_ev.x, mw_ev.y) / pixels_per_point,
+                    };
+
+                    if self.modifiers.ctrl || self.modifiers.command {
+                        raw_input
+                            .events
+                            .push(egui::Event::Zoom((delta.y / 200.0).exp()));
+                    } else if self.modifiers.shift {
+                        // Treat as horizontal scrolling.
+                        raw_input
+                            .events
+                            .push(egui::Event::Scroll(egui::vec2(delta.x + delta.y, 0.0)));
+                    } else {
+                        raw_input.events.push(egui::Event::Scroll(delta));
+                    }
+                }
+            }
+        }
+
+        // Controller navigation, egui already moves focus with the arrow keys and tab.
+        for gb_ev in events.gamepad_button.iter() {
+            let button = GamepadButton::new(gb_ev.gamepad, gb_ev.button_type);
+            let settings = gamepad_settings.get_button_settings(button);
+            let pressed = if gb_ev.value >= settings.press_threshold() {
+                true
+            } else if gb_ev.value <= settings.release_threshold() {
+                false
+            } else {
+                continue;
+            };
+
+            if let Some((key, modifiers)) = ui_key_from_gamepad_button(gb_ev.button_type) {
+                raw_input.events.push(egui::Event::Key {
+                    key,
+                    pressed,
+                    repeat: false,
+                    modifiers,
+                });
+            }
+        }
+
+        raw_input.modifiers = self.modifiers;
+        raw_input
+    }
+}
+
+/// Turns the cut, copy and paste shortcuts into their egui events.
+///
+/// A paste is left empty, see [`fill_paste_events`].
+fn ui_clipboard_event(key_code: KeyCode, modifiers: egui::Modifiers) -> Option<egui::Event> {
+    match key_code {
+        KeyCode::X if modifiers.command => Some(egui::Event::Cut),
+        KeyCode::Delete if modifiers.shift => Some(egui::Event::Cut),
+        KeyCode::C | KeyCode::Insert if modifiers.command => Some(egui::Event::Copy),
+        KeyCode::V if modifiers.command => Some(egui::Event::Paste(String::new())),
+        KeyCode::Insert if modifiers.shift => Some(egui::Event::Paste(String::new())),
+        _ => None,
+    }
+}
+
+/// Fills the empty pastes of [`UiInputTranslator::translate`] with the text of the clipboard.
+///
+/// `clipboard_text` is only called when there is a paste, and the pastes are dropped when the
+/// clipboard holds no text.
+pub fn fill_paste_events(
+    events: &mut Vec<egui::Event>,
+    clipboard_text: impl FnOnce() -> Option<String>,
+) {
+    fn is_empty_paste(event: &egui::Event) -> bool {
+        matches!(event, egui::Event::Paste(text) if text.is_empty())
+    }
+
+    if !events.iter().any(is_empty_paste) {
+        return;
+    }
+    let text = clipboard_text()
+        .map(|text| text.replace("\r\n", "\n"))
+        .unwrap_or_default();
+    if text.is_empty() {
+        events.retain(|event| !is_empty_paste(event));
+        return;
+    }
+    for event in events.iter_mut() {
+        if is_empty_paste(event) {
+            *event = egui::Event::Paste(text.clone());
+        }
+    }
+}
+
+pub fn ui_pointer_button_from_mouse_button(
+    mouse_button: MouseButton,
+) -> Option<egui::PointerButton> {
+    match mouse_button {
+        bevy_input::mouse::MouseButton::Left => Some(egui::PointerButton::Primary),
+        bevy_input::mouse::MouseButton::Right => Some(egui::PointerButton::Secondary),
+        bevy_input::mouse::MouseButton::Middle => Some(egui::PointerButton::Middle),
+        // `bevy_win32` reports `XBUTTON1` and `XBUTTON2` as `1` and `2`.
+        bevy_input::mouse::MouseButton::Other(1) => Some(egui::PointerButton::Extra1),
+        bevy_input::mouse::MouseButton::Other(2) => Some(egui::PointerButton::Extra2),
+        bevy_input::mouse::MouseButton::Other(_) => None,
+    }
+}
+
+/// Maps a gamepad button to the key (and modifiers) that performs the same navigation in egui.
+pub fn ui_key_from_gamepad_button(
+    button_type: GamepadButtonType,
+) -> Option<(egui::Key, egui::Modifiers)> {
+    match button_type {
+        GamepadButtonType::DPadUp => Some((egui::Key::ArrowUp, egui::Modifiers::NONE)),
+        GamepadButtonType::DPadDown => Some((egui::Key::ArrowDown, egui::Modifiers::NONE)),
+        GamepadButtonType::DPadLeft => Some((egui::Key::ArrowLeft, egui::Modifiers::NONE)),
+        GamepadButtonType::DPadRight => Some((egui::Key::ArrowRight, egui::Modifiers::NONE)),
+        GamepadButtonType::South => Some((egui::Key::Enter, egui::Modifiers::NONE)),
+        GamepadButtonType::East => Some((egui::Key::Escape, egui::Modifiers::NONE)),
+        GamepadButtonType::LeftTrigger => Some((egui::Key::Tab, egui::Modifiers::SHIFT)),
+        GamepadButtonType::RightTrigger => Some((egui::Key::Tab, egui::Modifiers::NONE)),
+        _ => None,
+    }
+}
+
+pub fn ui_key_from_key_code(keycode: KeyCode) -> Option<egui::Key> {
+    match keycode {
+        KeyCode::Key1 => Some(egui::Key::Num1),
+        KeyCode::Key2 => Some(egui::Key::Num2),
+        KeyCode::Key3 => Some(egui::Key::Num3),
+        KeyCode::Key4 => Some(egui::Key::Num4),
+        KeyCode::Key5 => Some(egui::Key::Num5),
+        KeyCode::Key6 => Some(egui::Key::Num6),
+        KeyCode::Key7 => Some(egui::Key::Num7),
+        KeyCode::Key8 => Some(egui::Key::Num8),
+        KeyCode::Key9 => Some(egui::Key::Num9),
+        KeyCode::Key0 => Some(egui::Key::Num0),
+        KeyCode::A => Some(egui::Key::A),
+        KeyCode::B => Some(egui::Key::B),
+        KeyCode::C => Some(egui::Key::C),
+        KeyCode::D => Some(egui::Key::D),
+        KeyCode::E => Some(egui::Key::E),
+        KeyCode::F => Some(egui::Key::F),
+        KeyCode::G => Some(egui::Key::G),
+        KeyCode::H => Some(egui::Key::H),
+        KeyCode::I => Some(egui::Key::I),
+        KeyCode::J => Some(egui::Key::J),
+        KeyCode::K => Some(egui::Key::K),
+        KeyCode::L => Some(egui::Key::L),
+        KeyCode::M => Some(egui::Key::M),
+        KeyCode::N => Some(egui::Key::N),
+        KeyCode::O => Some(egui::Key::O),
+        KeyCode::P => Some(egui::Key::P),
+        KeyCode::Q => Some(egui::Key::Q),
+        KeyCode::R => Some(egui::Key::R),
+        KeyCode::S => Some(egui::Key::S),
+        KeyCode::T => Some(egui::Key::T),
+        KeyCode::U => Some(egui::Key::U),
+        KeyCode::V => Some(egui::Key::V),
+        KeyCode::W => Some(egui::Key::W),
+        KeyCode::X => Some(egui::Key::X),
+        KeyCode::Y => Some(egui::Key::Y),
+        KeyCode::Z => Some(egui::Key::Z),
+        KeyCode::Escape => Some(egui::Key::Escape),
+        KeyCode::F1 => Some(egui::Key::F1),
+        KeyCode::F2 => Some(egui::Key::F2),
+        KeyCode::F3 => Some(egui::Key::F3),
+        KeyCode::F4 => Some(egui::Key::F4),
+        KeyCode::F5 => Some(egui::Key::F5),
+        KeyCode::F6 => Some(egui::Key::F6),
+        KeyCode::F7 => Some(egui::Key::F7),
+        KeyCode::F8 => Some(egui::Key::F8),
+        KeyCode::F9 => Some(egui::Key::F9),
+        KeyCode::F10 => Some(egui::Key::F10),
+        KeyCode::F11 => Some(egui::Key::F11),
+        KeyCode::F12 => Some(egui::Key::F12),
+        KeyCode::F13 => Some(egui::Key::F13),
+        KeyCode::F14 => Some(egui::Key::F14),
+        KeyCode::F15 => Some(egui::Key::F15),
+        KeyCode::F16 => Some(egui::Key::F16),
+        KeyCode::F17 => Some(egui::Key::F17),
+        KeyCode::F18 => Some(egui::Key::F18),
+        KeyCode::F19 => Some(egui::Key::F19),
+        KeyCode::F20 => Some(egui::Key::F20),
+        KeyCode::Insert => Some(egui::Key::Insert),
+        KeyCode::Home => Some(egui::Key::Home),
+        KeyCode::Delete => Some(egui::Key::Delete),
+        KeyCode::End => Some(egui::Key::End),
+        KeyCode::PageDown => Some(egui::Key::PageDown),
+        KeyCode::PageUp => Some(egui::Key::PageUp),
+        KeyCode::Left => Some(egui::Key::ArrowLeft),
+        KeyCode::Up => Some(egui::Key::ArrowUp),
+        KeyCode::Right => Some(egui::Key::ArrowRight),
+        KeyCode::Down => Some(egui::Key::ArrowDown),
+        KeyCode::Back => Some(egui::Key::Backspace),
+        KeyCode::Return => Some(egui::Key::Enter),
+        KeyCode::Space => Some(egui::Key::Space),
+        KeyCode::Compose => None,
+        KeyCode::Caret => None,
+        KeyCode::Numlock => None,
+        KeyCode::Numpad0 => Some(egui::Key::Num0),
+        KeyCode::Numpad1 => Some(egui::Key::Num1),
+        KeyCode::Numpad2 => Some(egui::Key::Num2),
+        KeyCode::Numpad3 => Some(egui::Key::Num3),
+        KeyCode::Numpad4 => Some(egui::Key::Num4),
+        KeyCode::Numpad5 => Some(egui::Key::Num5),
+        KeyCode::Numpad6 => Some(egui::Key::Num6),
+        KeyCode::Numpad7 => Some(egui::Key::Num7),
+        KeyCode::Numpad8 => Some(egui::Key::Num8),
+        KeyCode::Numpad9 => Some(egui::Key::Num9),
+        KeyCode::Equals => Some(egui::Key::PlusEquals),
+        KeyCode::Tab => Some(egui::Key::Tab),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_input::{
+        keyboard::KeyboardInput,
+        mouse::{MouseButtonInput, MouseWheel},
+        ButtonState,
+    };
+    use bevy_math::Vec2;
+    use bevy_window::{CursorMoved, ReceivedCharacter};
+
+    use super::*;
+
+    const WINDOW: Entity = Entity::PLACEHOLDER;
+
+    fn window_state(pixels_per_point: f32, focused: bool) -> UiWindowState {
+        UiWindowState {
+            size: egui::vec2(800.0, 600.0),
+            pixels_per_point,
+            focused,
+        }
+    }
+
+    fn state(pressed: bool) -> ButtonState {
+        match pressed {
+            true => ButtonState::Pressed,
+            false => ButtonState::Released,
+        }
+    }
+
+    fn key(key_code: KeyCode, pressed: bool) -> Win32InputEvent {
+        Win32InputEvent::Keyboard(KeyboardInput {
+            scan_code: 0,
+            key_code: Some(key_code),
+            state: state(pressed),
+            window: WINDOW,
+        })
+    }
+
+    fn character(char: char) -> Win32InputEvent {
+        Win32InputEvent::Character(ReceivedCharacter {
+            window: WINDOW,
+            char,
+        })
+    }
+
+    fn wheel(unit: MouseScrollUnit, y: f32) -> Win32InputEvent {
+        Win32InputEvent::MouseWheel(MouseWheel {
+            unit,
+            x: 0.0,
+            y,
+            window: WINDOW,
+        })
+    }
+
+    fn translate_with(
+        translator: &mut UiInputTranslator,
+        window: Option<UiWindowState>,
+        events: Vec<Win32InputEvent>,
+    ) -> RawInput {
+        let events = UiInputEvents {
+            window: events,
+            gamepad_button: Vec::new(),
+        };
+        translator.translate(window, 0.0, &events, &GamepadSettings::default())
+    }
+
+    fn translate(translator: &mut UiInputTranslator, events: Vec<Win32InputEvent>) -> RawInput {
+        translate_with(translator, Some(window_state(1.0, true)), events)
+    }
+
+    fn key_event(key: egui::Key, pressed: bool, modifiers: egui::Modifiers) -> egui::Event {
+        egui::Event::Key {
+            key,
+            pressed,
+            repeat: false,
+            modifiers,
+        }
+    }
+
+    #[test]
+    fn keys() {
+        let mut translator = UiInputTranslator::default();
+        let raw_input = translate(
+            &mut translator,
+            vec![
+                key(KeyCode::A, true),
+                key(KeyCode::A, false),
+                key(KeyCode::Numlock, true),
+            ],
+        );
+        assert_eq!(
+            raw_input.events,
+            vec![
+                key_event(egui::Key::A, true, egui::Modifiers::NONE),
+                key_event(egui::Key::A, false, egui::Modifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn modifiers_are_held_between_frames() {
+        let mut translator = UiInputTranslator::default();
+        let ctrl = egui::Modifiers {
+            ctrl: true,
+            command: true,
+            ..Default::default()
+        };
+
+        let raw_input = translate(&mut translator, vec![key(KeyCode::ControlLeft, true)]);
+        assert_eq!(raw_input.modifiers, ctrl);
+
+        let raw_input = translate(&mut translator, vec![key(KeyCode::S, true)]);
+        assert_eq!(raw_input.events, vec![key_event(egui::Key::S, true, ctrl)]);
+        assert_eq!(raw_input.modifiers, ctrl);
+
+        let raw_input = translate(
+            &mut translator,
+            vec![key(KeyCode::ControlLeft, false), key(KeyCode::S, true)],
+        );
+        assert_eq!(
+            raw_input.events,
+            vec![key_event(egui::Key::S, true, egui::Modifiers::NONE)]
+        );
+        assert_eq!(raw_input.modifiers, egui::Modifiers::NONE);
+    }
+
+    #[test]
+    fn events_keep_their_order() {
+        let mut translator = UiInputTranslator::default();
+        let raw_input = translate(
+            &mut translator,
+            vec![
+                character('a'),
+                key(KeyCode::Return, true),
+                Win32InputEvent::CursorMoved(CursorMoved {
+                    window: WINDOW,
+                    position: Vec2::new(10.0, 20.0),
+                }),
+                character('b'),
+            ],
+        );
+        assert_eq!(
+            raw_input.events,
+            vec![
+                egui::Event::Text("a".to_owned()),
+                key_event(egui::Key::Enter, true, egui::Modifiers::NONE),
+                egui::Event::PointerMoved(egui::pos2(10.0, 20.0)),
+                egui::Event::Text("b".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_is_suppressed_while_ctrl_is_held() {
+        let mut translator = UiInputTranslator::default();
+        let raw_input = translate(
+            &mut translator,
+            vec![
+                key(KeyCode::ControlLeft, true),
+                character('a'),
+                key(KeyCode::ControlLeft, false),
+                character('b'),
+            ],
+        );
+        let texts: Vec<_> = raw_input
+            .events
+            .iter()
+            .filter(|event| matches!(event, egui::Event::Text(_)))
+            .collect();
+        assert_eq!(texts, vec![&egui::Event::Text("b".to_owned())]);
+    }
+
+    #[test]
+    fn wheel_scrolls_and_zooms() {
+        let mut translator = UiInputTranslator::default();
+        let raw_input = translate_with(
+            &mut translator,
+            Some(window_state(2.0, true)),
+            vec![
+                wheel(MouseScrollUnit::Line, 1.0),
+                wheel(MouseScrollUnit::Pixel, 120.0),
+                key(KeyCode::ShiftLeft, true),
+                wheel(MouseScrollUnit::Line, 1.0),
+                key(KeyCode::ShiftLeft, false),
+                key(KeyCode::ControlLeft, true),
+                wheel(MouseScrollUnit::Pixel, 0.0),
+            ],
+        );
+        let wheel_events: Vec<_> = raw_input
+            .events
+            .into_iter()
+            .filter(|event| matches!(event, egui::Event::Scroll(_) | egui::Event::Zoom(_)))
+            .collect();
+        assert_eq!(
+            wheel_events,
+            vec![
+                egui::Event::Scroll(egui::vec2(0.0, POINTS_PER_SCROLL_LINE)),
+                egui::Event::Scroll(egui::vec2(0.0, 60.0)),
+                egui::Event::Scroll(egui::vec2(POINTS_PER_SCROLL_LINE, 0.0)),
+                egui::Event::Zoom(1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn x_buttons_press_at_the_pointer() {
+        let mut translator = UiInputTranslator::default();
+        let button = |button, pressed| {
+            Win32InputEvent::MouseButton(MouseButtonInput {
+                button,
+                state: state(pressed),
+                window: WINDOW,
+            })
+        };
+        translate(
+            &mut translator,
+            vec![Win32InputEvent::CursorMoved(CursorMoved {
+                window: WINDOW,
+                position: Vec2::new(5.0, 6.0),
+            })],
+        );
+        let raw_input = translate(
+            &mut translator,
+            vec![
+                button(MouseButton::Other(1), true),
+                button(MouseButton::Other(2), false),
+                button(MouseButton::Other(3), true),
+            ],
+        );
+        let pointer_button = |button, pressed| egui::Event::PointerButton {
+            pos: egui::pos2(5.0, 6.0),
+            button,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+        assert_eq!(
+            raw_input.events,
+            vec![
+                pointer_button(egui::PointerButton::Extra1, true),
+                pointer_button(egui::PointerButton::Extra2, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn focus_follows_the_window() {
+        let mut translator = UiInputTranslator::default();
+        let unfocused = translate_with(&mut translator, Some(window_state(1.0, false)), vec![]);
+        assert!(!unfocused.focused);
+        let focused = translate_with(&mut translator, Some(window_state(1.0, true)), vec![]);
+        assert!(focused.focused);
+        let no_window = translate_with(&mut translator, None, vec![]);
+        assert!(no_window.focused);
+        assert_eq!(no_window.screen_rect, None);
+    }
+
+    #[test]
+    fn paste_is_filled_from_the_clipboard() {
+        let mut translator = UiInputTranslator::default();
+        let mut raw_input = translate(
+            &mut translator,
+            vec![key(KeyCode::ControlLeft, true), key(KeyCode::V, true)],
+        );
+        assert_eq!(raw_input.events[0], egui::Event::Paste(String::new()));
+
+        fill_paste_events(&mut raw_input.events, || Some("a\r\nb".to_owned()));
+        assert_eq!(raw_input.events[0], egui::Event::Paste("a\nb".to_owned()));
+
+        let mut events = vec![egui::Event::Paste(String::new())];
+        fill_paste_events(&mut events, || None);
+        assert!(events.is_empty());
+
+        let mut events = vec![egui::Event::Copy];
+        fill_paste_events(&mut events, || panic!("read the clipboard without a paste"));
+        assert_eq!(events, vec![egui::Event::Copy]);
+    }
+
+    #[test]
+    fn clipboard_shortcuts() {
+        let ctrl = egui::Modifiers {
+            ctrl: true,
+            command: true,
+            ..Default::default()
+        };
+        let shift = egui::Modifiers::SHIFT;
+        let none = egui::Modifiers::NONE;
+        let paste = || Some(egui::Event::Paste(String::new()));
+
+        assert_eq!(
+            ui_clipboard_event(KeyCode::C, ctrl),
+            Some(egui::Event::Copy)
+        );
+        assert_eq!(
+            ui_clipboard_event(KeyCode::Insert, ctrl),
+            Some(egui::Event::Copy)
+        );
+        assert_eq!(ui_clipboard_event(KeyCode::X, ctrl), Some(egui::Event::Cut));
+        assert_eq!(
+            ui_clipboard_event(KeyCode::Delete, shift),
+            Some(egui::Event::Cut)
+        );
+        assert_eq!(ui_clipboard_event(KeyCode::V, ctrl), paste());
+        assert_eq!(ui_clipboard_event(KeyCode::Insert, shift), paste());
+
+        assert_eq!(ui_clipboard_event(KeyCode::C, none), None);
+        assert_eq!(ui_clipboard_event(KeyCode::V, shift), None);
+        assert_eq!(ui_clipboard_event(KeyCode::Delete, none), None);
+        assert_eq!(ui_clipboard_event(KeyCode::Insert, none), None);
+    }
+}
@@ -0,0 +1,331 @@
+//! Routes the egui [`egui::PlatformOutput`] of the ui through ECS events.
+//!
+//! [`ui_end_frame`](super::ui_end_frame) only sends the events, the handlers in this module are
+//! what apply them to the game window, the clipboard, etc... Other plugins can read the same
+//! events to react to them as well.
+
+use std::time::{Duration, Instant};
+
+use bevy_ecs::{prelude::*, system::SystemParam};
+use bevy_win32::clipboard::Clipboard;
+use bevy_window::{CursorIcon, Window};
+
+use crate::utils;
+
+use super::PrimaryUi;
+
+/// Sent when the cursor icon requested by the ui of a window changes.
+#[derive(Debug, Clone, Copy, PartialEq, Event)]
+pub struct UiCursorIconChanged {
+    pub window: Entity,
+    pub icon: egui::CursorIcon,
+}
+
+/// Sent when the ui of a window copied or cut some text.
+#[derive(Debug, Clone, PartialEq, Event)]
+pub struct UiCopiedText {
+    pub window: Entity,
+    pub text: String,
+}
+
+/// Sent when a link in the ui of a window was clicked.
+#[derive(Clone, PartialEq, Event)]
+pub struct UiOpenUrl {
+    pub window: Entity,
+    pub open_url: egui::OpenUrl,
+}
+
+/// Sent when the text cursor of the focused text edit moves.
+#[derive(Debug, Clone, Copy, PartialEq, Event)]
+pub struct UiImeCursor {
+    pub window: Entity,
+    /// The cursor position in points, `None` when no text edit has focus.
+    pub position: Option<egui::Pos2>,
+}
+
+/// Sent at the end of every ui frame with how long egui is happy to wait for the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Event)]
+pub struct UiRepaintAfter {
+    pub window: Entity,
+    pub after: Duration,
+}
+
+/// The parts of the last [`egui::PlatformOutput`] that events are only sent for on change.
+#[derive(Debug, Default)]
+pub struct UiLastOutput {
+    cursor_icon: egui::CursorIcon,
+    ime_cursor: Option<egui::Pos2>,
+}
+
+#[derive(SystemParam)]
+pub struct UiOutputEventWriters<'w> {
+    cursor_icon: EventWriter<'w, UiCursorIconChanged>,
+    copied_text: EventWriter<'w, UiCopiedText>,
+    open_url: EventWriter<'w, UiOpenUrl>,
+    ime_cursor: EventWriter<'w, UiImeCursor>,
+    repaint_after: EventWriter<'w, UiRepaintAfter>,
+}
+
+/// Sends the events for everything in `output` that other systems should react to.
+pub fn send_platform_output(
+    window: Entity,
+    output: egui::PlatformOutput,
+    repaint_after: Duration,
+    last: &mut UiLastOutput,
+    writers: &mut UiOutputEventWriters,
+) {
+    if output.cursor_icon != last.cursor_icon {
+        last.cursor_icon = output.cursor_icon;
+        writers.cursor_icon.send(UiCursorIconChanged {
+            window,
+            icon: output.cursor_icon,
+        });
+    }
+    if !output.copied_text.is_empty() {
+        writers.copied_text.send(UiCopiedText {
+            window,
+            text: output.copied_text,
+        });
+    }
+    if let Some(open_url) = output.open_url {
+        writers.open_url.send(UiOpenUrl { window, open_url });
+    }
+    if output.text_cursor_pos != last.ime_cursor {
+        last.ime_cursor = output.text_cursor_pos;
+        writers.ime_cursor.send(UiImeCursor {
+            window,
+            position: output.text_cursor_pos,
+        });
+    }
+    writers.repaint_after.send(UiRepaintAfter {
+        window,
+        after: repaint_after,
+    });
+}
+
+pub fn apply_cursor_icon(
+    mut ev_cursor_icon: EventReader<UiCursorIconChanged>,
+    mut windows: Query<&mut Window>,
+) {
+    for ev in ev_cursor_icon.iter() {
+        let Ok(mut window) = windows.get_mut(ev.window) else {
+            continue;
+        };
+        match cursor_icon_from_egui(ev.icon) {
+            Some(icon) => {
+                window.cursor.icon = icon;
+                window.cursor.visible = true;
+            }
+            None => window.cursor.visible = false,
+        }
+    }
+}
+
+pub fn copy_to_clipboard(
+    mut ev_copied_text: EventReader<UiCopiedText>,
+    mut clipboard: ResMut<Clipboard>,
+) {
+    if let Some(ev) = ev_copied_text.iter().last() {
+        clipboard.set_text(&ev.text);
+    }
+}
+
+pub fn apply_ime_cursor(
+    mut ev_ime_cursor: EventReader<UiImeCursor>,
+    mut windows: Query<&mut Window>,
+) {
+    for ev in ev_ime_cursor.iter() {
+        let Ok(mut window) = windows.get_mut(ev.window) else {
+            continue;
+        };
+        window.ime_enabled = ev.position.is_some();
+        if let Some(pos) = ev.position {
+            window.ime_position = bevy_math::Vec2::new(pos.x, pos.y);
+        }
+    }
+}
+
+/// The url waiting for the user to confirm that it should be opened.
+#[derive(Default, Resource)]
+pub struct PendingOpenUrl(Option<egui::OpenUrl>);
+
+pub fn queue_open_url(
+    mut ev_open_url: EventReader<UiOpenUrl>,
+    mut pending: ResMut<PendingOpenUrl>,
+) {
+    if let Some(ev) = ev_open_url.iter().last() {
+        pending.0 = Some(ev.open_url.clone());
+    }
+}
+
+/// Asks the user to confirm opening the [`PendingOpenUrl`], since we are running inside of a game.
+pub fn ui_confirm_open_url(mut primary_ui: PrimaryUi, mut pending: ResMut<PendingOpenUrl>) {
+    let Some(open_url) = &pending.0 else {
+        return;
+    };
+    let Some(ctx) = primary_ui.ctx_mut() else {
+        return;
+    };
+
+    let mut answer = None;
+    egui::Window::new("Open link?")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("This will open the following link in your browser:");
+            ui.monospace(&open_url.url);
+            ui.horizontal(|ui| {
+                if ui.button("Open").clicked() {
+                    answer = Some(true);
+                }
+                if ui.button("Cancel").clicked() {
+                    answer = Some(false);
+                }
+            });
+        });
+
+    match answer {
+        Some(true) => {
+            if !utils::open_url(&open_url.url) {
+                log::warn!("failed to open {}", open_url.url);
+            }
+            pending.0 = None;
+        }
+        Some(false) => pending.0 = None,
+        None => {}
+    }
+}
+
+/// Whether the ui runs this frame, following egui's `repaint_after`.
+///
+/// On frames the ui is skipped, the last frame is presented again.
+#[derive(Debug, Resource)]
+pub struct UiRepaint {
+    /// When the next repaint is due, `None` if egui only wants to repaint on input.
+    repaint_at: Option<Instant>,
+    repainting: bool,
+}
+
+impl Default for UiRepaint {
+    fn default() -> Self {
+        Self {
+            repaint_at: Some(Instant::now()),
+            repainting: true,
+        }
+    }
+}
+
+impl UiRepaint {
+    /// Whether egui asked to be repainted by now, even without any new input.
+    pub fn is_due(&self) -> bool {
+        self.repaint_at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    pub fn is_repainting(&self) -> bool {
+        self.repainting
+    }
+
+    pub fn set_repainting(&mut self, repainting: bool) {
+        self.repainting = repainting;
+    }
+
+    /// Repaints on the next frame, for changes egui does not know about.
+    pub fn request(&mut self) {
+        self.repaint_at = Some(Instant::now());
+    }
+}
+
+/// Schedules the next repaint for the soonest `repaint_after` of all the ui contexts.
+pub fn schedule_repaint(
+    mut ev_repaint_after: EventReader<UiRepaintAfter>,
+    mut repaint: ResMut<UiRepaint>,
+) {
+    if ev_repaint_after.is_empty() {
+        return;
+    }
+    let now = Instant::now();
+    repaint.repaint_at = ev_repaint_after
+        .iter()
+        .filter_map(|ev| now.checked_add(ev.after))
+        .min();
+}
+
+/// Run condition for the ui systems that only run when the ui is repainting.
+pub fn ui_repainting(repaint: Res<UiRepaint>) -> bool {
+    repaint.is_repainting()
+}
+
+/// Maps an egui cursor icon to the bevy one, `None` if the cursor should be hidden.
+pub fn cursor_icon_from_egui(icon: egui::CursorIcon) -> Option<CursorIcon> {
+    use egui::CursorIcon as Egui;
+    Some(match icon {
+        Egui::None => return None,
+        Egui::Default => CursorIcon::Default,
+        Egui::ContextMenu => CursorIcon::ContextMenu,
+        Egui::Help => CursorIcon::Help,
+        Egui::PointingHand => CursorIcon::Hand,
+        Egui::Progress => CursorIcon::Progress,
+        Egui::Wait => CursorIcon::Wait,
+        Egui::Cell => CursorIcon::Cell,
+        Egui::Crosshair => CursorIcon::Crosshair,
+        Egui::Text => CursorIcon::Text,
+        Egui::VerticalText => CursorIcon::VerticalText,
+        Egui::Alias => CursorIcon::Alias,
+        Egui::Copy => CursorIcon::Copy,
+        Egui::Move => CursorIcon::Move,
+        Egui::NoDrop => CursorIcon::NoDrop,
+        Egui::NotAllowed => CursorIcon::NotAllowed,
+        Egui::Grab => CursorIcon::Grab,
+        Egui::Grabbing => CursorIcon::Grabbing,
+        Egui::AllScroll => CursorIcon::AllScroll,
+        Egui::ResizeHorizontal => CursorIcon::EwResize,
+        Egui::ResizeNeSw => CursorIcon::NeswResize,
+        Egui::ResizeNwSe => CursorIcon::NwseResize,
+        Egui::ResizeVertical => CursorIcon::NsResize,
+        Egui::ResizeEast => CursorIcon::EResize,
+        Egui::ResizeSouthEast => CursorIcon::SeResize,
+        Egui::ResizeSouth => CursorIcon::SResize,
+        Egui::ResizeSouthWest => CursorIcon::SwResize,
+        Egui::ResizeWest => CursorIcon::WResize,
+        Egui::ResizeNorthWest => CursorIcon::NwResize,
+        Egui::ResizeNorth => CursorIcon::NResize,
+        Egui::ResizeNorthEast => CursorIcon::NeResize,
+        Egui::ResizeColumn => CursorIcon::ColResize,
+        Egui::ResizeRow => CursorIcon::RowResize,
+        Egui::ZoomIn => CursorIcon::ZoomIn,
+        Egui::ZoomOut => CursorIcon::ZoomOut,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_win32::clipboard::MemoryClipboard;
+
+    use crate::ui::input::fill_paste_events;
+
+    use super::*;
+
+    #[test]
+    fn copied_text_round_trip() {
+        let mut world = World::new();
+        world.insert_resource(Clipboard::new(MemoryClipboard::default()));
+        world.init_resource::<Events<UiCopiedText>>();
+        let mut schedule = Schedule::new();
+        schedule.add_systems(copy_to_clipboard);
+
+        for text in ["first", "second"] {
+            world.send_event(UiCopiedText {
+                window: Entity::PLACEHOLDER,
+                text: text.to_owned(),
+            });
+        }
+        schedule.run(&mut world);
+
+        // Only the last copy of a frame ends up on the clipboard.
+        let mut clipboard = world.resource_mut::<Clipboard>();
+        let mut events = vec![egui::Event::Paste(String::new())];
+        fill_paste_events(&mut events, || clipboard.get_text());
+        assert_eq!(events, vec![egui::Event::Paste("second".to_owned())]);
+    }
+}
@@ -0,0 +1,187 @@
+//! Keeps the ui state, like window positions and collapsed headers, between injections.
+//!
+//! The state is written to [`UI_STATE_FILE`] in the config directory on uninject and every
+//! [`UiPersistence::save_interval`], and restored into the [`PrimaryUiContext`] when it is created.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use bevy_app::AppExit;
+use bevy_ecs::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::utils;
+
+use super::{PrimaryUiContext, UiContext};
+
+pub const UI_STATE_FILE: &str = "ui.ron";
+
+/// Bump this whenever [`UiStateFile`] changes in a way older files can't be read as.
+const UI_STATE_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum UiStateError {
+    #[error("Io error {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to parse ui state {0}")]
+    Parse(#[from] ron::error::SpannedError),
+
+    #[error("Failed to serialize ui state {0}")]
+    Serialize(#[from] ron::Error),
+
+    #[error("Ui state version {0} is not supported, expected {UI_STATE_VERSION}")]
+    UnsupportedVersion(u32),
+}
+
+/// Read first, so that we know the version before trying to read the rest.
+#[derive(Deserialize)]
+struct UiStateHeader {
+    version: u32,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct UiStateFile {
+    version: u32,
+    memory: Option<egui::Memory>,
+    /// Panel state of other plugins, as ron strings so one broken panel can't break the rest.
+    panels: BTreeMap<String, String>,
+}
+
+impl UiStateFile {
+    fn read(path: &Path) -> Result<Option<Self>, UiStateError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let header: UiStateHeader = ron::from_str(&text)?;
+        if header.version != UI_STATE_VERSION {
+            return Err(UiStateError::UnsupportedVersion(header.version));
+        }
+        Ok(Some(ron::from_str(&text)?))
+    }
+
+    fn write(&self, path: &Path) -> Result<(), UiStateError> {
+        let text = ron::ser::to_string_pretty(self, Default::default())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write next to it first, so a crash mid write doesn't leave a broken file behind.
+        let tmp_path = path.with_extension("ron.tmp");
+        fs::write(&tmp_path, text)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+/// The persisted ui state, and where it is saved to.
+#[derive(Resource)]
+pub struct UiPersistence {
+    /// `None` if there is nowhere to save to, in which case nothing is persisted.
+    path: Option<PathBuf>,
+    pub save_interval: Duration,
+    last_save: Instant,
+    /// The loaded memory, until it is restored into the primary ui context.
+    memory: Option<egui::Memory>,
+    panels: BTreeMap<String, String>,
+}
+
+impl UiPersistence {
+    /// Loads the state saved at `path`.
+    ///
+    /// A file that is corrupt or from an unsupported version is moved aside and we start fresh.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let file = path
+            .as_deref()
+            .and_then(|path| match UiStateFile::read(path) {
+                Ok(file) => file,
+                Err(err) => {
+                    log::warn!("discarding ui state {}: {}", path.display(), err);
+                    let _ = fs::rename(path, path.with_extension("ron.bak"));
+                    None
+                }
+            });
+        let file = file.unwrap_or_default();
+
+        Self {
+            path,
+            save_interval: Duration::from_secs(30),
+            last_save: Instant::now(),
+            memory: file.memory,
+            panels: file.panels,
+        }
+    }
+
+    /// Reads the state a panel saved with [`UiPersistence::set_panel`].
+    pub fn panel<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let text = self.panels.get(key)?;
+        ron::from_str(text)
+            .map_err(|err| log::warn!("discarding ui state of panel {}: {}", key, err))
+            .ok()
+    }
+
+    /// Sets the state of a panel, it is written out with the next save.
+    pub fn set_panel<T: Serialize>(&mut self, key: &str, value: &T) {
+        match ron::to_string(value) {
+            Ok(text) => {
+                self.panels.insert(key.to_owned(), text);
+            }
+            Err(err) => log::warn!("failed to serialize ui state of panel {}: {}", key, err),
+        }
+    }
+
+    /// Takes the loaded egui memory, only the first caller gets it.
+    pub fn take_memory(&mut self) -> Option<egui::Memory> {
+        self.memory.take()
+    }
+
+    pub fn save(&mut self, memory: Option<egui::Memory>) -> Result<(), UiStateError> {
+        self.last_save = Instant::now();
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        UiStateFile {
+            version: UI_STATE_VERSION,
+            memory,
+            panels: self.panels.clone(),
+        }
+        .write(path)
+    }
+}
+
+impl Default for UiPersistence {
+    fn default() -> Self {
+        Self::load(utils::config_dir().map(|dir| dir.join(UI_STATE_FILE)))
+    }
+}
+
+/// Run condition for saving the ui state, every [`UiPersistence::save_interval`] and on exit.
+///
+/// Plugins that keep their panel state elsewhere can use it to store it right before the save.
+pub fn ui_state_save_due(
+    persistence: Res<UiPersistence>,
+    mut ev_exit: EventReader<AppExit>,
+) -> bool {
+    let exiting = ev_exit.iter().count() > 0;
+    exiting || persistence.last_save.elapsed() >= persistence.save_interval
+}
+
+pub fn save_ui_state(
+    mut persistence: ResMut<UiPersistence>,
+    primary_ui_ctx: Query<&UiContext, With<PrimaryUiContext>>,
+) {
+    let memory = primary_ui_ctx
+        .get_single()
+        .ok()
+        .map(|ui_ctx| ui_ctx.ctx().memory(|memory| memory.clone()))
+        // Keep what we loaded if it never made it into a ui context.
+        .or_else(|| persistence.memory.clone());
+    if let Err(err) = persistence.save(memory) {
+        log::warn!("failed to save ui state: {}", err);
+    }
+}
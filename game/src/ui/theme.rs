@@ -0,0 +1,399 @@
+//! Themes for the ui and painter, loaded from [`THEME_FILE`] in the config directory.
+//!
+//! The file either names one of the [`ThemePreset`]s, e.g. `Light`, or is a full [`Theme`] where
+//! left out fields fall back to the [`ThemePreset::Dark`] preset. It is reloaded whenever it
+//! changes on disk.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use bevy_ecs::prelude::*;
+use egui::{Color32, FontFamily, FontId, Rounding, Stroke, TextStyle};
+use epaint::Shadow;
+use serde::{Deserialize, Serialize};
+
+use crate::{paint::PainterContext, utils};
+
+use super::{fonts::Fonts, UiContext};
+
+pub const THEME_FILE: &str = "theme.ron";
+
+/// How often the theme file is checked for changes.
+const THEME_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A colour written as `"#rrggbb"` or `"#rrggbbaa"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThemeColor(pub Color32);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        parse_hex_color(&value)
+            .map(ThemeColor)
+            .ok_or_else(|| format!("invalid colour {:?}, expected #rrggbb or #rrggbbaa", value))
+    }
+}
+
+impl From<ThemeColor> for String {
+    fn from(color: ThemeColor) -> Self {
+        let [r, g, b, a] = color.0.to_srgba_unmultiplied();
+        match a {
+            u8::MAX => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            _ => format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a),
+        }
+    }
+}
+
+/// Parses `"#rrggbb"` or `"#rrggbbaa"`, with the alpha unmultiplied.
+pub fn parse_hex_color(hex: &str) -> Option<Color32> {
+    let hex = hex.strip_prefix('#')?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+    let alpha = match hex.len() {
+        8 => channel(3)?,
+        _ => u8::MAX,
+    };
+    Some(Color32::from_rgba_unmultiplied(
+        channel(0)?,
+        channel(1)?,
+        channel(2)?,
+        alpha,
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeFontFamily {
+    Proportional,
+    Monospace,
+    /// A family registered with the fonts, by name.
+    Named(String),
+}
+
+impl From<&ThemeFontFamily> for FontFamily {
+    fn from(family: &ThemeFontFamily) -> Self {
+        match family {
+            ThemeFontFamily::Proportional => FontFamily::Proportional,
+            ThemeFontFamily::Monospace => FontFamily::Monospace,
+            ThemeFontFamily::Named(name) => FontFamily::Name(name.as_str().into()),
+        }
+    }
+}
+
+/// Font sizes in points.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeFontSizes {
+    pub small: f32,
+    pub body: f32,
+    pub button: f32,
+    pub heading: f32,
+    pub monospace: f32,
+}
+
+impl Default for ThemeFontSizes {
+    fn default() -> Self {
+        Self {
+            small: 9.0,
+            body: 12.5,
+            button: 12.5,
+            heading: 18.0,
+            monospace: 12.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Picks egui's dark or light visuals as the base the rest is applied on.
+    pub dark_mode: bool,
+    /// Overrides the text colour of every widget.
+    pub text_color: Option<ThemeColor>,
+    pub window_fill: ThemeColor,
+    pub panel_fill: ThemeColor,
+    pub window_stroke: ThemeColor,
+    /// Used for selections and hyperlinks.
+    pub accent: ThemeColor,
+    pub window_shadow: bool,
+    pub rounding: f32,
+    pub item_spacing: [f32; 2],
+    pub button_padding: [f32; 2],
+    pub window_margin: f32,
+    /// The family of every text style but monospace.
+    pub font_family: ThemeFontFamily,
+    pub font_sizes: ThemeFontSizes,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        ThemePreset::Dark.theme()
+    }
+}
+
+impl Theme {
+    pub fn apply(&self, style: &mut egui::Style) {
+        let mut visuals = match self.dark_mode {
+            true => egui::Visuals::dark(),
+            false => egui::Visuals::light(),
+        };
+        visuals.override_text_color = self.text_color.map(|color| color.0);
+        visuals.window_fill = self.window_fill.0;
+        visuals.panel_fill = self.panel_fill.0;
+        visuals.window_stroke = Stroke {
+            width: 1.0,
+            color: self.window_stroke.0,
+        };
+        visuals.selection.bg_fill = self.accent.0;
+        visuals.hyperlink_color = self.accent.0;
+        if !self.window_shadow {
+            visuals.window_shadow = Shadow::NONE;
+            visuals.popup_shadow = Shadow::NONE;
+        }
+        let rounding = Rounding::same(self.rounding);
+        visuals.window_rounding = rounding;
+        visuals.menu_rounding = rounding;
+        for widget in [
+            &mut visuals.widgets.noninteractive,
+            &mut visuals.widgets.inactive,
+            &mut visuals.widgets.hovered,
+            &mut visuals.widgets.active,
+            &mut visuals.widgets.open,
+        ] {
+            widget.rounding = rounding;
+        }
+        style.visuals = visuals;
+
+        style.spacing.item_spacing = self.item_spacing.into();
+        style.spacing.button_padding = self.button_padding.into();
+        style.spacing.window_margin = egui::Margin::same(self.window_margin);
+
+        let family = FontFamily::from(&self.font_family);
+        let sizes = &self.font_sizes;
+        style.text_styles = [
+            (TextStyle::Small, FontId::new(sizes.small, family.clone())),
+            (TextStyle::Body, FontId::new(sizes.body, family.clone())),
+            (TextStyle::Button, FontId::new(sizes.button, family.clone())),
+            (TextStyle::Heading, FontId::new(sizes.heading, family)),
+            (
+                TextStyle::Monospace,
+                FontId::new(sizes.monospace, FontFamily::Monospace),
+            ),
+        ]
+        .into();
+    }
+
+    /// Falls back to [`ThemeFontFamily::Proportional`] if the font family is not one of the
+    /// `fonts`, logging a warning.
+    pub fn check_font_family(&mut self, fonts: &Fonts) {
+        if !fonts.has_family(&FontFamily::from(&self.font_family)) {
+            log::warn!(
+                "theme font family {:?} is not loaded, using the proportional fonts",
+                self.font_family
+            );
+            self.font_family = ThemeFontFamily::Proportional;
+        }
+    }
+
+    /// Loads a theme file, see the [module docs](self) for the format.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        if let Ok(preset) = ron::from_str::<ThemePreset>(&text) {
+            return Ok(preset.theme());
+        }
+        ron::from_str(&text).map_err(|err| err.to_string())
+    }
+}
+
+/// The themes that ship with the overlay.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl ThemePreset {
+    pub fn theme(self) -> Theme {
+        let base = Theme {
+            dark_mode: true,
+            text_color: None,
+            window_fill: ThemeColor(Color32::from_gray(27)),
+            panel_fill: ThemeColor(Color32::from_gray(27)),
+            window_stroke: ThemeColor(Color32::from_gray(60)),
+            accent: ThemeColor(Color32::from_rgb(0, 92, 128)),
+            window_shadow: false,
+            rounding: 6.0,
+            item_spacing: [8.0, 3.0],
+            button_padding: [4.0, 1.0],
+            window_margin: 6.0,
+            font_family: ThemeFontFamily::Proportional,
+            font_sizes: Default::default(),
+        };
+
+        match self {
+            ThemePreset::Dark => base,
+            ThemePreset::Light => Theme {
+                dark_mode: false,
+                window_fill: ThemeColor(Color32::from_gray(248)),
+                panel_fill: ThemeColor(Color32::from_gray(248)),
+                window_stroke: ThemeColor(Color32::from_gray(190)),
+                accent: ThemeColor(Color32::from_rgb(144, 209, 255)),
+                ..base
+            },
+            ThemePreset::HighContrast => Theme {
+                text_color: Some(ThemeColor(Color32::WHITE)),
+                window_fill: ThemeColor(Color32::BLACK),
+                panel_fill: ThemeColor(Color32::BLACK),
+                window_stroke: ThemeColor(Color32::WHITE),
+                accent: ThemeColor(Color32::from_rgb(255, 200, 0)),
+                rounding: 0.0,
+                font_sizes: ThemeFontSizes {
+                    small: 11.0,
+                    body: 14.0,
+                    button: 14.0,
+                    heading: 20.0,
+                    monospace: 14.0,
+                },
+                ..base
+            },
+        }
+    }
+}
+
+/// The active [`Theme`] of the ui and painter.
+///
+/// Follows the theme file when there is one.
+#[derive(Resource)]
+pub struct UiTheme {
+    theme: Theme,
+    path: Option<PathBuf>,
+    last_modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl UiTheme {
+    /// Loads the theme file at `path`, if any, with its font family checked against `fonts`.
+    pub fn new(path: Option<PathBuf>, fonts: &Fonts) -> Self {
+        let mut ui_theme = Self {
+            theme: Theme::default(),
+            path,
+            last_modified: None,
+            last_poll: Instant::now(),
+        };
+        ui_theme.reload_if_modified(fonts);
+        ui_theme
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Loads the theme file if it changed since we last read it, returning whether it did.
+    ///
+    /// A file that fails to load is logged and the current theme is kept.
+    fn reload_if_modified(&mut self, fonts: &Fonts) -> bool {
+        self.last_poll = Instant::now();
+        let Some(path) = &self.path else {
+            return false;
+        };
+        let Ok(modified) = fs::metadata(path).and_then(|meta| meta.modified()) else {
+            return false;
+        };
+        if self.last_modified == Some(modified) {
+            return false;
+        }
+        self.last_modified = Some(modified);
+
+        match Theme::load(path) {
+            Ok(mut theme) => {
+                log::info!("loaded theme {}", path.display());
+                theme.check_font_family(fonts);
+                self.theme = theme;
+                true
+            }
+            Err(err) => {
+                log::warn!("failed to load theme {}: {}", path.display(), err);
+                false
+            }
+        }
+    }
+}
+
+impl FromWorld for UiTheme {
+    fn from_world(world: &mut World) -> Self {
+        world.init_resource::<Fonts>();
+        Self::new(
+            utils::config_dir().map(|dir| dir.join(THEME_FILE)),
+            world.resource::<Fonts>(),
+        )
+    }
+}
+
+pub fn reload_theme(mut ui_theme: ResMut<UiTheme>, fonts: Res<Fonts>) {
+    if ui_theme.bypass_change_detection().last_poll.elapsed() < THEME_POLL_INTERVAL {
+        return;
+    }
+    if !ui_theme
+        .bypass_change_detection()
+        .reload_if_modified(&fonts)
+    {
+        return;
+    }
+    ui_theme.set_changed();
+}
+
+/// Applies the theme to new ui and painter contexts, and to all of them when it changes.
+pub fn apply_theme(
+    ui_theme: Res<UiTheme>,
+    ui_contexts: Query<Ref<UiContext>>,
+    painter_contexts: Query<Ref<PainterContext>>,
+) {
+    let theme = ui_theme.theme();
+    for ui_ctx in ui_contexts.iter() {
+        if ui_theme.is_changed() || ui_ctx.is_added() {
+            ui_ctx.ctx().style_mut(|style| theme.apply(style));
+        }
+    }
+    for painter_ctx in painter_contexts.iter() {
+        if ui_theme.is_changed() || painter_ctx.is_added() {
+            painter_ctx.ctx().style_mut(|style| theme.apply(style));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_font_family_falls_back() {
+        let mut fonts = Fonts::builtin();
+        fonts.push_to_family(FontFamily::Name("Loaded".into()), "Loaded");
+
+        let mut theme = Theme {
+            font_family: ThemeFontFamily::Named("Loaded".to_owned()),
+            ..Default::default()
+        };
+        theme.check_font_family(&fonts);
+        assert_eq!(
+            theme.font_family,
+            ThemeFontFamily::Named("Loaded".to_owned())
+        );
+
+        theme.font_family = ThemeFontFamily::Named("Missing".to_owned());
+        theme.check_font_family(&fonts);
+        assert_eq!(theme.font_family, ThemeFontFamily::Proportional);
+
+        theme.font_family = ThemeFontFamily::Monospace;
+        theme.check_font_family(&fonts);
+        assert_eq!(theme.font_family, ThemeFontFamily::Monospace);
+    }
+}
@@ -1,8 +1,9 @@
-use std::{fs::OpenOptions, os::windows::prelude::AsRawHandle};
+use std::{fs::OpenOptions, os::windows::prelude::AsRawHandle, path::PathBuf};
 
 use windows::Win32::{
     Foundation::{BOOL, HANDLE, HINSTANCE, HWND, LPARAM, RECT},
     System::{
+        Com::CoTaskMemFree,
         Console::{
             AllocConsole, GetConsoleWindow, SetStdHandle, STD_ERROR_HANDLE, STD_OUTPUT_HANDLE,
         },
@@ -10,12 +11,18 @@ use windows::Win32::{
         LibraryLoader::GetModuleHandleA,
         SystemServices::IMAGE_DOS_HEADER,
     },
-    UI::WindowsAndMessaging::{
-        EnumWindows, GetWindow, GetWindowLongA, GetWindowRect, GetWindowThreadProcessId,
-        GWL_HINSTANCE, GW_OWNER,
+    UI::{
+        Shell::{FOLDERID_RoamingAppData, SHGetKnownFolderPath, ShellExecuteW, KF_FLAG_DEFAULT},
+        WindowsAndMessaging::{
+            ClipCursor, EnumWindows, GetWindow, GetWindowLongA, GetWindowRect,
+            GetWindowThreadProcessId, ShowCursor, GWL_HINSTANCE, GW_OWNER, SW_SHOWNORMAL,
+        },
     },
 };
 
+/// The name of our directory in the users config directory.
+pub const CONFIG_DIR_NAME: &str = "cs2-overlay";
+
 #[macro_export]
 macro_rules! c_str {
     ($string:expr) => {
@@ -93,6 +100,67 @@ pub fn get_window_hwnd() -> Option<HWND> {
     }
 }
 
+/// Opens `url` with the default handler, e.g. the users browser.
+///
+/// Only `http` and `https` urls are opened, anything else could launch a file or program.
+pub fn open_url(url: &str) -> bool {
+    if !is_web_url(url) {
+        return false;
+    }
+    let url = windows::core::HSTRING::from(url);
+    let result = unsafe {
+        ShellExecuteW(
+            HWND(0),
+            windows::core::w!("open"),
+            &url,
+            windows::core::PCWSTR::null(),
+            windows::core::PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Anything above 32 is a success.
+    result.0 > 32
+}
+
+/// Whether `url` has an `http` or `https` scheme.
+fn is_web_url(url: &str) -> bool {
+    url.split_once("://").is_some_and(|(scheme, rest)| {
+        !rest.is_empty()
+            && (scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https"))
+    })
+}
+
+/// Frees the cursor from the rectangle it is clipped to.
+pub fn unclip_cursor() {
+    let _ = unsafe { ClipCursor(None) };
+}
+
+/// Shows the cursor of the calling thread, returning how many times the display counter had to be incremented.
+pub fn show_cursor() -> u32 {
+    let mut count = 1;
+    while unsafe { ShowCursor(true) } < 0 {
+        count += 1;
+    }
+    count
+}
+
+/// Undoes a [`show_cursor`] that incremented the display counter `count` times.
+pub fn hide_cursor(count: u32) {
+    for _ in 0..count {
+        unsafe { ShowCursor(false) };
+    }
+}
+
+/// Where we keep our config and state, `%APPDATA%/cs2-overlay`.
+pub fn config_dir() -> Option<PathBuf> {
+    unsafe {
+        let path = SHGetKnownFolderPath(&FOLDERID_RoamingAppData, KF_FLAG_DEFAULT, None).ok()?;
+        let dir = path.to_string();
+        CoTaskMemFree(Some(path.0 as _));
+        Some(PathBuf::from(dir.ok()?).join(CONFIG_DIR_NAME))
+    }
+}
+
 pub fn get_module(module_name: &str) -> Option<HINSTANCE> {
     unsafe { Some(GetModuleHandleA(win_pcstr!("{}", module_name)).ok()?.into()) }
 }